@@ -0,0 +1,168 @@
+// Typed puzzle answers, so they can be compared, stored and serialized structurally
+use crate::vec2::Vec2;
+use crate::vec3::Vec3;
+use serde_json::{json, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Integer(i64),
+    String(String),
+    Coordinate(Vec<i64>),
+    Text(String), // multi-line answers, like letters drawn in a grid
+}
+
+impl Answer {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Answer::Integer(_) => "integer",
+            Answer::String(_) => "string",
+            Answer::Coordinate(_) => "coordinate",
+            Answer::Text(_) => "text",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            Answer::Integer(value) => json!(value),
+            Answer::Coordinate(values) => json!(values),
+            Answer::String(s) | Answer::Text(s) => json!(s),
+        }
+    }
+
+    pub fn from_json(value: &Value) -> Option<Answer> {
+        match value {
+            Value::Number(n) => n.as_i64().map(Answer::Integer),
+            Value::Array(values) => values
+                .iter()
+                .map(Value::as_i64)
+                .collect::<Option<Vec<_>>>()
+                .map(Answer::Coordinate),
+            Value::String(s) => Some(Answer::from(s.as_str())),
+            _ => None,
+        }
+    }
+
+    // Answers stored before they were typed are plain strings, so compare
+    // those by their formatted value. Some days used to explain their result,
+    // as in "37 * 31 = 1147", where only the final number is the answer.
+    pub fn matches(&self, expected: &Answer) -> bool {
+        if self == expected {
+            return true;
+        }
+        match (self, expected) {
+            (Answer::String(_), _) | (Answer::Text(_), _) => false,
+            (_, Answer::String(legacy)) => {
+                if self.to_string() == *legacy {
+                    return true;
+                }
+                match self {
+                    Answer::Integer(value) => legacy
+                        .rsplit(|c| c == '=' || c == '>')
+                        .next()
+                        .and_then(|result| result.split_whitespace().next())
+                        .and_then(|result| result.parse::<i64>().ok())
+                        .map_or(false, |result| result == *value && legacy.contains('=')),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Integer(value) => write!(f, "{}", value),
+            Answer::Coordinate(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                Ok(())
+            }
+            Answer::String(s) | Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! impl_integer_answer {
+    ($($t:ty),+) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(value: $t) -> Answer {
+                    Answer::Integer(value as i64)
+                }
+            }
+        )+
+    };
+}
+impl_integer_answer!(i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! impl_coordinate_answer {
+    ($($t:ty),+) => {
+        $(
+            impl From<Vec2<$t>> for Answer {
+                fn from(value: Vec2<$t>) -> Answer {
+                    Answer::Coordinate(vec![value.x as i64, value.y as i64])
+                }
+            }
+            impl From<Vec3<$t>> for Answer {
+                fn from(value: Vec3<$t>) -> Answer {
+                    Answer::Coordinate(vec![value.x as i64, value.y as i64, value.z as i64])
+                }
+            }
+        )+
+    };
+}
+impl_coordinate_answer!(i32, i64, isize, usize);
+
+// Strings are never parsed as numbers, answers like "0123" must stay intact
+impl From<String> for Answer {
+    fn from(value: String) -> Answer {
+        if value.contains('\n') {
+            Answer::Text(value)
+        } else {
+            Answer::String(value)
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Answer {
+    fn from(value: &'a str) -> Answer {
+        Answer::from(value.to_owned())
+    }
+}
+
+#[test]
+fn answer_test() {
+    use crate::vec2::Vec2us;
+
+    assert_eq!(Answer::from(42usize), Answer::Integer(42));
+    assert_eq!(Answer::from(Vec2us::new(7, 3)), Answer::Coordinate(vec![7, 3]));
+    assert_eq!(Answer::from("0123"), Answer::String("0123".to_owned()));
+    assert_eq!(Answer::from("#.\n.#").kind(), "text");
+    assert_eq!(Answer::Coordinate(vec![233, 288, 16]).to_string(), "233,288,16");
+
+    for answer in &[
+        Answer::Integer(-5),
+        Answer::String("abc".to_owned()),
+        Answer::Coordinate(vec![1, 2]),
+        Answer::Text("#.\n.#".to_owned()),
+    ] {
+        assert_eq!(Answer::from_json(&answer.to_json()).as_ref(), Some(answer));
+    }
+
+    // Answers stored as strings by older versions
+    assert!(Answer::Integer(1147).matches(&Answer::from("1147")));
+    assert!(Answer::Integer(1147).matches(&Answer::from("37 * 31 = 1147")));
+    assert!(Answer::Integer(51).matches(&Answer::from("1570 => 51")));
+    assert!(Answer::Integer(4988).matches(&Answer::from("47 * 106 = 4988 (15 attack power)")));
+    assert!(Answer::Coordinate(vec![7, 3]).matches(&Answer::from("7,3")));
+    assert!(!Answer::Integer(1147).matches(&Answer::from("1148")));
+    assert!(!Answer::Integer(31).matches(&Answer::from("37 * 31 = 1147")));
+    assert!(!Answer::String("12".to_owned()).matches(&Answer::Integer(12)));
+}
@@ -0,0 +1,296 @@
+// Directory based input cache, one plain text file per input plus an index
+use crate::{Error, Result};
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const INDEX_FILE: &str = "index.json";
+const LEGACY_CACHE_FILE: &str = "cache.dat";
+
+#[derive(Debug, Clone)]
+pub struct InputCache {
+    dir: PathBuf,
+    index: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub file: String,
+    pub fetched_at: u64, // seconds since the unix epoch
+    pub hash: String,    // hex encoded SHA-256 of the content
+}
+
+pub fn content_hash(content: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input_str(content);
+    hasher.result_str()
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// Inputs are namespaced per year, like 2018/day05.txt, and the inputs of other
+// profiles per profile, like 2018/alice/day05.txt
+fn file_name_for(url: &str) -> String {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(
+            r"/(?P<year>\d+)/day/(?P<day>\d+)(?P<input>/input)?(#(?P<profile>[\w-]+))?$"
+        )
+        .unwrap();
+    }
+
+    let parsed = RE.captures(url).and_then(|c| {
+        let year = c["year"].parse::<u32>().ok()?;
+        let day = c["day"].parse::<u32>().ok()?;
+        let dir = match c.name("profile") {
+            Some(profile) => format!("{}/{}", year, profile.as_str()),
+            None => year.to_string(),
+        };
+        Some((dir, day, c.name("input").is_some()))
+    });
+    match parsed {
+        Some((dir, day, true)) => format!("{}/day{:02}.txt", dir, day),
+        // Puzzle descriptions are cached next to their input
+        Some((dir, day, false)) => format!("{}/day{:02}.html", dir, day),
+        None => format!("{}.txt", &content_hash(url)[..16]),
+    }
+}
+
+pub fn format_timestamp(timestamp: u64) -> String {
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+impl Entry {
+    fn to_json(&self) -> Value {
+        json!({
+            "file": self.file,
+            "fetched_at": self.fetched_at,
+            "hash": self.hash,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Entry> {
+        Some(Entry {
+            file: value.get("file")?.as_str()?.to_owned(),
+            fetched_at: value.get("fetched_at")?.as_u64()?,
+            hash: value.get("hash")?.as_str()?.to_owned(),
+        })
+    }
+}
+
+impl InputCache {
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<InputCache> {
+        let mut cache = InputCache {
+            dir: dir.as_ref().to_owned(),
+            index: BTreeMap::new(),
+        };
+
+        match fs::read(cache.dir.join(INDEX_FILE)) {
+            Ok(data) => {
+                let index: Value = ::serde_json::from_slice(&data[..])?;
+                let index = index
+                    .as_object()
+                    .ok_or(Error::Cache("cache index must be a JSON object"))?;
+                for (url, entry) in index {
+                    let entry =
+                        Entry::from_json(entry).ok_or(Error::Cache("invalid cache index entry"))?;
+                    cache.index.insert(url.clone(), entry);
+                }
+            }
+            Err(ref e) if e.kind() == ErrorKind::NotFound => cache.migrate_legacy_cache()?,
+            Err(e) => return Err(e.into()),
+        }
+        Ok(cache)
+    }
+
+    // The legacy cache file sits next to the cache directory
+    fn migrate_legacy_cache(&mut self) -> Result<()> {
+        let legacy_path = self.dir.with_file_name(LEGACY_CACHE_FILE);
+        let data = match fs::read(&legacy_path) {
+            Ok(data) => data,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let legacy: HashMap<String, String> = ::bincode::deserialize(&data[..])?;
+        for (url, content) in legacy {
+            self.insert(&url, &content)?;
+        }
+        let backup_path = self.dir.with_file_name(format!("{}.bak", LEGACY_CACHE_FILE));
+        fs::rename(&legacy_path, &backup_path)?;
+        eprintln!(
+            "migrated {} to {} (old file kept as {})",
+            legacy_path.display(),
+            self.dir.display(),
+            backup_path.display()
+        );
+        Ok(())
+    }
+
+    fn save_index(&self) -> Result<()> {
+        let index = self
+            .index
+            .iter()
+            .map(|(url, entry)| (url.clone(), entry.to_json()))
+            .collect::<::serde_json::Map<_, _>>();
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.dir.join(INDEX_FILE),
+            ::serde_json::to_string_pretty(&Value::Object(index))?,
+        )?;
+        Ok(())
+    }
+
+    pub fn contains(&self, url: &str) -> bool {
+        self.index.contains_key(url)
+    }
+
+    pub fn entries<'a>(&'a self) -> impl Iterator<Item = (&'a str, &'a Entry)> + 'a {
+        self.index.iter().map(|(url, entry)| (url.as_str(), entry))
+    }
+
+    pub fn path(&self, entry: &Entry) -> PathBuf {
+        self.dir.join(&entry.file)
+    }
+
+    pub fn file_path(&self, url: &str) -> Option<PathBuf> {
+        self.index.get(url).map(|entry| self.path(entry))
+    }
+
+    pub fn get(&self, url: &str) -> Result<Option<String>> {
+        match self.index.get(url) {
+            Some(entry) => match fs::read_to_string(self.path(entry)) {
+                Ok(content) => Ok(Some(content)),
+                Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn is_modified(&self, url: &str) -> Result<bool> {
+        match (self.index.get(url), self.get(url)?) {
+            (Some(entry), Some(content)) => Ok(content_hash(&content) != entry.hash),
+            _ => Ok(false),
+        }
+    }
+
+    pub fn insert(&mut self, url: &str, content: &str) -> Result<()> {
+        let entry = Entry {
+            file: file_name_for(url),
+            fetched_at: now(),
+            hash: content_hash(content),
+        };
+        let path = self.path(&entry);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+        self.index.insert(url.to_owned(), entry);
+        self.save_index()
+    }
+
+    pub fn invalidate(&mut self, url: &str) -> Result<bool> {
+        match self.index.remove(url) {
+            Some(entry) => {
+                match fs::remove_file(self.path(&entry)) {
+                    Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+                    result => result?,
+                }
+                self.save_index()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        let urls = self.index.keys().cloned().collect::<Vec<_>>();
+        for url in urls {
+            self.invalidate(&url)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn cache_test() {
+    assert_eq!(
+        file_name_for("https://adventofcode.com/2018/day/5/input"),
+        "2018/day05.txt"
+    );
+    assert_eq!(
+        file_name_for("https://adventofcode.com/2017/day/25/input#alice"),
+        "2017/alice/day25.txt"
+    );
+    assert_eq!(file_name_for("https://adventofcode.com/2018/day/5"), "2018/day05.html");
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+    assert_eq!(format_timestamp(951_868_799), "2000-02-29 23:59:59 UTC");
+
+    let root = ::std::env::temp_dir().join(format!("aoc_cache_test_{}", ::std::process::id()));
+    let dir = root.join("cache");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    // A legacy cache.dat next to the cache directory is moved into it
+    let url = "https://adventofcode.com/2018/day/1/input";
+    let legacy = vec![(url.to_owned(), "+1\n-2\n".to_owned())]
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+    fs::write(root.join(LEGACY_CACHE_FILE), ::bincode::serialize(&legacy).unwrap()).unwrap();
+    let mut cache = InputCache::open(&dir).unwrap();
+    assert_eq!(cache.get(url).unwrap().unwrap(), "+1\n-2\n");
+    assert!(dir.join("2018/day01.txt").exists());
+    assert!(root.join("cache.dat.bak").exists());
+    assert!(!root.join(LEGACY_CACHE_FILE).exists());
+
+    let other = "https://adventofcode.com/2018/day/2/input#alice";
+    assert_eq!(cache.get(other).unwrap(), None);
+    cache.insert(other, "abcdef\n").unwrap();
+    assert!(!cache.is_modified(other).unwrap());
+    fs::write(dir.join("2018/alice/day02.txt"), "edited\n").unwrap();
+    assert!(cache.is_modified(other).unwrap());
+
+    // The index is read back when the cache is opened again
+    let mut cache = InputCache::open(&dir).unwrap();
+    assert_eq!(cache.entries().count(), 2);
+    assert_eq!(cache.get(other).unwrap().unwrap(), "edited\n");
+    assert!(cache.invalidate(url).unwrap());
+    assert!(!cache.invalidate(url).unwrap());
+    assert!(!dir.join("2018/day01.txt").exists());
+    assert!(!InputCache::open(&dir).unwrap().contains(url));
+
+    fs::write(dir.join(INDEX_FILE), "[]").unwrap();
+    assert!(InputCache::open(&dir).is_err());
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,746 @@
+use crate::debugger;
+use crate::decompiler;
+use crate::elfcode::Program;
+use crate::fetch::Fetcher;
+use crate::memory;
+use crate::watch;
+use crate::framework::{
+    self, DayId, Framework, InputSource, MatrixCell, OutputFormat, PartResult, Parts, Status,
+};
+use crate::{Error, Result};
+use reqwest::Client;
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "usage:
+    advent_of_code_2018 [run] [DAYS...] [OPTIONS]
+    advent_of_code_2018 bench [DAYS...] [OPTIONS] [--iterations N]
+    advent_of_code_2018 verify [DAYS...] [--part 1|2] [--cached] [--format text|json]
+    advent_of_code_2018 confirm [DAYS...] [--part 1|2] [--cached] [--format text|json]
+    advent_of_code_2018 examples [DAYS...] [--part 1|2] [--cached] [--format text|json]
+    advent_of_code_2018 examples show DAY [--cached]
+    advent_of_code_2018 submit DAY [--part 1|2] [--cached]
+    advent_of_code_2018 watch DAY [--part 1|2] [--input FILE] [--cached] [--interval MS]
+    advent_of_code_2018 debug DAY [--input FILE] [--cached]
+    advent_of_code_2018 decompile DAY [--input FILE|-] [--cached]
+    advent_of_code_2018 fetch [DAYS...]
+    advent_of_code_2018 cache list|clear
+    advent_of_code_2018 cache show DAY
+    advent_of_code_2018 cache invalidate DAYS...
+    advent_of_code_2018 cache import DAY FILE
+    advent_of_code_2018 list
+
+OPTIONS are --part 1|2, --input FILE|-, --cached and --format text|json.
+Every command accepts --offline to fail instead of accessing the network,
+--rate-limit SECONDS between requests (default 5) and --retries N for server
+errors (default 3).
+--session TOKEN overrides the session token from AOC_SESSION, token.txt or the
+config file, and --base-url URL (or AOC_BASE_URL) downloads from another server.
+run, verify and confirm also accept --parallel to solve all parts on worker
+threads once the inputs are loaded, one per CPU unless given with --jobs N.
+DAYS is a list of day names (day05, or just 5) and inclusive ranges (day03..day07).
+--profile NAME uses the session token and inputs of a profile from the config
+file instead of the default one. run, verify, confirm and fetch accept several
+--profile options, or --all-profiles, to compare their inputs in a matrix, but
+not together with --input.
+Days belong to the latest event unless prefixed with another (2017/day05), or
+given with --year YEAR. When no days are given, every registered day is
+selected, or every day of --year.
+--input reads the puzzle input from FILE, or from stdin when FILE is -.
+--cached only uses previously downloaded inputs and never fetches.
+--timeout SECONDS gives up on a part that runs longer (default 60, 0 waits
+forever). Parts that time out or panic are reported as TIMEOUT or PANIC, the
+remaining parts still run.
+--memory counts the heap allocations of every part, and reports the peak heap
+usage, the bytes allocated and the number of allocations. run, verify and
+confirm accept it, but not together with --parallel, and parts are then never
+given up on.
+--format json prints one JSON object per part instead of colored text.
+confirm records the current answers as expected, verify fails on any mismatch.
+examples solves the examples from the puzzle descriptions and fails on any
+mismatch, examples show lists the code blocks and answers of a description.
+submit solves a part and submits its answer, part 1 first unless --part is
+given. Verdicts are kept in submissions.json, answers that are known to be wrong
+are never submitted twice and the server's cooldown is waited out.
+watch runs a day again whenever its input file or answers.json changes, checking
+every --interval milliseconds (default 500), and shows what changed.
+debug runs the ElfCode program in a day's input (like day19 or day21) in an
+interactive debugger, with breakpoints, register watches and a trace log. Type
+help at its prompt for the commands. decompile prints such a program as
+pseudocode with loops and ifs instead of jumps.";
+
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    pub command: Command,
+    pub network: NetworkOptions,
+    pub profiles: Vec<String>, // several profiles compare their inputs in a matrix
+    pub timeout: Option<Duration>,
+    pub memory: bool, // count the allocations of every part
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NetworkOptions {
+    pub offline: bool,
+    pub min_interval: Option<Duration>,
+    pub retries: Option<u32>,
+    pub session: Option<String>,
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    Run(RunOptions),
+    Bench(RunOptions, usize),
+    Watch(RunOptions, Duration),
+    Verify(RunOptions),
+    Confirm(RunOptions),
+    Examples(RunOptions),
+    ShowExamples(DayId, InputSource),
+    Debug(DayId, InputSource),
+    Decompile(DayId, InputSource),
+    Submit(DayId, Parts, InputSource),
+    Fetch(Vec<DayId>),
+    Cache(CacheCommand),
+    List,
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheCommand {
+    List,
+    Show(DayId),
+    Invalidate(Vec<DayId>),
+    Import(DayId, PathBuf),
+    Clear,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub days: Vec<DayId>,
+    pub parts: Parts,
+    pub source: InputSource,
+    pub format: OutputFormat,
+    pub parallel: bool,
+    pub jobs: Option<usize>,
+}
+
+fn usage_error<T>(message: String) -> Result<T> {
+    Err(Error::Usage(message))
+}
+
+// Days are given as day05 or 5, optionally prefixed with their event like
+// 2018/day05. Without an event they belong to `year`.
+fn parse_day_id(name: &str, year: Option<u16>) -> Result<DayId> {
+    let (year, day) = match name.rfind('/') {
+        Some(i) => (name[..i].parse::<u16>().ok(), &name[i + 1..]),
+        None => (year, name),
+    };
+    match (year, day.trim_start_matches("day").parse::<u8>()) {
+        (Some(year), Ok(day)) => Ok(DayId::new(year, day)),
+        _ => Err(Error::DayDoesNotExist(name.to_owned())),
+    }
+}
+
+fn resolve_days(fw: &Framework, selection: &[String], year: Option<u16>) -> Result<Vec<DayId>> {
+    if selection.is_empty() {
+        return Ok(fw
+            .day_ids()
+            .filter(|id| year.map_or(true, |year| id.year == year))
+            .collect());
+    }
+
+    let mut days = Vec::new();
+    for item in selection {
+        let mut range = item.splitn(2, "..");
+        let start = range.next().unwrap();
+        match range.next() {
+            Some(end) => {
+                let start = resolve_day(fw, start, year)?;
+                let end = resolve_day(fw, end, year)?;
+                days.extend(fw.day_ids().filter(|&id| id >= start && id <= end));
+            }
+            None => days.push(resolve_day(fw, start, year)?),
+        }
+    }
+    // Keeps the first mention of every day, in the order given
+    let mut seen = HashSet::new();
+    days.retain(|&id| seen.insert(id));
+    Ok(days)
+}
+
+fn resolve_day(fw: &Framework, day: &str, year: Option<u16>) -> Result<DayId> {
+    let id = parse_day_id(day, year.or_else(|| fw.latest_year()))?;
+    if fw.has_day(id) {
+        Ok(id)
+    } else {
+        Err(Error::DayDoesNotExist(id.to_string()))
+    }
+}
+
+pub fn parse<I>(fw: &Framework, args: I) -> Result<Invocation>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+    let command = match args.peek().map(String::as_str) {
+        Some("run") | Some("bench") | Some("verify") | Some("confirm") | Some("examples")
+        | Some("submit") | Some("watch") | Some("debug") | Some("decompile") | Some("fetch")
+        | Some("cache") | Some("list") | Some("help") => {
+            args.next().unwrap()
+        }
+        _ => "run".to_owned(),
+    };
+
+    let mut selection = Vec::new();
+    let mut parts = Parts::Both;
+    let mut source = InputSource::Remote;
+    let mut iterations = None;
+    let mut interval = None;
+    let mut timeout = Some(Duration::from_secs(framework::DEFAULT_TIMEOUT_SECS));
+    let mut has_timeout = false;
+    let mut has_input = false;
+    let mut memory = false;
+    let mut format = OutputFormat::Text;
+    let mut parallel = false;
+    let mut jobs = None;
+    let mut year = None;
+    let mut profiles = Vec::new();
+    let mut network = NetworkOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                return Ok(Invocation {
+                    command: Command::Help,
+                    network,
+                    profiles,
+                    timeout,
+                    memory,
+                });
+            }
+            "--offline" => network.offline = true,
+            "--rate-limit" => match args.next().map(|n| n.parse::<f64>()) {
+                Some(Ok(seconds)) if seconds >= 0.0 => {
+                    network.min_interval = Some(Duration::from_millis((seconds * 1000.0) as u64));
+                }
+                _ => return usage_error("--rate-limit expects a number of seconds".to_owned()),
+            },
+            "--session" => match args.next() {
+                Some(token) => network.session = Some(token),
+                None => return usage_error("--session expects a token".to_owned()),
+            },
+            "--base-url" => match args.next() {
+                Some(url) => network.base_url = Some(url),
+                None => return usage_error("--base-url expects a URL".to_owned()),
+            },
+            "--retries" => match args.next().map(|n| n.parse::<u32>()) {
+                Some(Ok(n)) => network.retries = Some(n),
+                _ => return usage_error("--retries expects a number".to_owned()),
+            },
+            "--profile" => match args.next() {
+                Some(profile) => profiles.push(profile),
+                None => return usage_error("--profile expects a profile name".to_owned()),
+            },
+            "--all-profiles" => profiles.extend(fw.profile_names()),
+            "--year" => match args.next().map(|n| n.parse::<u16>()) {
+                Some(Ok(n)) => year = Some(n),
+                _ => return usage_error("--year expects a year".to_owned()),
+            },
+            "--part" => {
+                parts = match args.next().as_ref().map(String::as_str) {
+                    Some("1") => Parts::Part1,
+                    Some("2") => Parts::Part2,
+                    _ => return usage_error("--part expects 1 or 2".to_owned()),
+                }
+            }
+            "--input" => {
+                has_input = true;
+                match args.next() {
+                    Some(ref path) if path == "-" => source = InputSource::Stdin,
+                    Some(path) => source = InputSource::File(PathBuf::from(path)),
+                    None => return usage_error("--input expects a file path".to_owned()),
+                }
+            }
+            "--cached" => source = InputSource::Cache,
+            "--format" => {
+                format = match args.next().as_ref().map(String::as_str) {
+                    Some("text") => OutputFormat::Text,
+                    Some("json") => OutputFormat::Json,
+                    _ => return usage_error("--format expects text or json".to_owned()),
+                }
+            }
+            "--parallel" => parallel = true,
+            "--jobs" | "-j" => match args.next().map(|n| n.parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => {
+                    parallel = true;
+                    jobs = Some(n);
+                }
+                _ => return usage_error("--jobs expects a positive number".to_owned()),
+            },
+            "--interval" => match args.next().map(|n| n.parse::<u64>()) {
+                Some(Ok(n)) if n > 0 => interval = Some(Duration::from_millis(n)),
+                _ => return usage_error("--interval expects a number of milliseconds".to_owned()),
+            },
+            "--timeout" => match args.next().map(|n| n.parse::<f64>()) {
+                Some(Ok(seconds)) if seconds >= 0.0 => {
+                    has_timeout = true;
+                    timeout = if seconds > 0.0 {
+                        Some(Duration::from_millis((seconds * 1000.0) as u64))
+                    } else {
+                        None
+                    };
+                }
+                _ => return usage_error("--timeout expects a number of seconds".to_owned()),
+            },
+            "--memory" => memory = true,
+            "--iterations" => match args.next().map(|n| n.parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => iterations = Some(n),
+                _ => return usage_error("--iterations expects a positive number".to_owned()),
+            },
+            flag if flag.starts_with("--") => {
+                return usage_error(format!("unknown option {}", flag));
+            }
+            _ => selection.push(arg),
+        }
+    }
+
+    let allows_run_options = match command.as_str() {
+        "run" | "bench" | "verify" | "confirm" | "examples" | "submit" | "watch" | "debug"
+        | "decompile" => true,
+        _ => false,
+    };
+    if !allows_run_options
+        && (parts != Parts::Both || source != InputSource::Remote || format != OutputFormat::Text)
+    {
+        return usage_error(format!(
+            "{} does not accept --part, --input, --cached or --format",
+            command
+        ));
+    }
+    if !allows_run_options && has_timeout {
+        return usage_error(format!("{} does not accept --timeout", command));
+    }
+    let mut seen = HashSet::new();
+    profiles.retain(|profile| seen.insert(profile.clone()));
+    // Every profile would run the same input, or only the first one stdin
+    if profiles.len() > 1 && has_input {
+        return usage_error("--input does not accept several profiles".to_owned());
+    }
+    if profiles.len() > 1 {
+        match command.as_str() {
+            "run" | "verify" | "confirm" | "fetch" if !parallel => {}
+            _ => {
+                return usage_error(format!(
+                    "{} does not accept several profiles{}",
+                    command,
+                    if parallel { " with --parallel" } else { "" }
+                ));
+            }
+        }
+    }
+    if parallel && !(command == "run" || command == "verify" || command == "confirm") {
+        return usage_error(format!("{} does not accept --parallel or --jobs", command));
+    }
+    if memory && (parallel || !(command == "run" || command == "verify" || command == "confirm")) {
+        return usage_error(format!(
+            "{} does not accept --memory{}",
+            command,
+            if parallel { " with --parallel" } else { "" }
+        ));
+    }
+    // A part that timed out keeps allocating while the next ones are counted
+    if memory && has_timeout && timeout.is_some() {
+        return usage_error("--memory does not accept --timeout, other than 0".to_owned());
+    }
+    if memory {
+        timeout = None;
+    }
+    if command != "bench" && iterations.is_some() {
+        return usage_error("--iterations is only valid for bench".to_owned());
+    }
+    if command != "watch" && interval.is_some() {
+        return usage_error("--interval is only valid for watch".to_owned());
+    }
+
+    let command = match command.as_str() {
+        "help" => Command::Help,
+        "list" => {
+            if !selection.is_empty() {
+                return usage_error("list does not accept arguments".to_owned());
+            }
+            Command::List
+        }
+        "cache" => Command::Cache(match selection.split_first() {
+            Some((action, rest)) => match (action.as_str(), rest) {
+                ("list", []) => CacheCommand::List,
+                ("clear", []) => CacheCommand::Clear,
+                ("show", [day]) => CacheCommand::Show(resolve_day(fw, day, year)?),
+                ("invalidate", days) if !days.is_empty() => {
+                    CacheCommand::Invalidate(resolve_days(fw, days, year)?)
+                }
+                ("import", [day, path]) => {
+                    CacheCommand::Import(resolve_day(fw, day, year)?, PathBuf::from(path))
+                }
+                _ => return usage_error(format!("invalid arguments for cache {}", action)),
+            },
+            None => return usage_error("cache expects an action".to_owned()),
+        }),
+        "fetch" => Command::Fetch(resolve_days(fw, &selection, year)?),
+        "watch" => match (selection.as_slice(), &source, format) {
+            ([_], InputSource::Stdin, _) => {
+                return usage_error("watch cannot read the input from stdin".to_owned());
+            }
+            ([_], _, OutputFormat::Json) => {
+                return usage_error("watch does not accept --format json".to_owned());
+            }
+            ([day], _, _) => Command::Watch(
+                RunOptions {
+                    days: vec![resolve_day(fw, day, year)?],
+                    parts,
+                    source,
+                    format,
+                    parallel: false,
+                    jobs: None,
+                },
+                interval.unwrap_or(Duration::from_millis(watch::DEFAULT_INTERVAL_MS)),
+            ),
+            _ => return usage_error("watch expects exactly one day".to_owned()),
+        },
+        "debug" => match (selection.as_slice(), &source) {
+            ([_], InputSource::Stdin) => {
+                return usage_error("debug reads its commands from stdin, not the input".to_owned());
+            }
+            ([day], _) if parts == Parts::Both && format == OutputFormat::Text && !has_timeout => {
+                Command::Debug(resolve_day(fw, day, year)?, source)
+            }
+            ([_], _) => return usage_error("debug only accepts --input and --cached".to_owned()),
+            _ => return usage_error("debug expects exactly one day".to_owned()),
+        },
+        "decompile" => match selection.as_slice() {
+            [day] if parts == Parts::Both && format == OutputFormat::Text && !has_timeout => {
+                Command::Decompile(resolve_day(fw, day, year)?, source)
+            }
+            [_] => return usage_error("decompile only accepts --input and --cached".to_owned()),
+            _ => return usage_error("decompile expects exactly one day".to_owned()),
+        },
+        "submit" => match (selection.as_slice(), &source, format) {
+            ([day], InputSource::Remote, OutputFormat::Text)
+            | ([day], InputSource::Cache, OutputFormat::Text) => {
+                Command::Submit(resolve_day(fw, day, year)?, parts, source)
+            }
+            ([_], _, _) => return usage_error("submit only accepts --part and --cached".to_owned()),
+            _ => return usage_error("submit expects exactly one day".to_owned()),
+        },
+        "examples" => {
+            if let InputSource::File(_) | InputSource::Stdin = source {
+                return usage_error("examples does not accept --input".to_owned());
+            }
+            match selection.split_first() {
+                Some((action, rest)) if action == "show" => match rest {
+                    [day] if parts == Parts::Both && format == OutputFormat::Text => {
+                        Command::ShowExamples(resolve_day(fw, day, year)?, source)
+                    }
+                    [_] => return usage_error("examples show only accepts --cached".to_owned()),
+                    _ => return usage_error("examples show expects a day".to_owned()),
+                },
+                _ => Command::Examples(RunOptions {
+                    days: resolve_days(fw, &selection, year)?,
+                    parts,
+                    source,
+                    format,
+                    parallel: false,
+                    jobs: None,
+                }),
+            }
+        }
+        _ => {
+            let days = resolve_days(fw, &selection, year)?;
+            let is_local = match source {
+                InputSource::File(_) | InputSource::Stdin => true,
+                InputSource::Cache | InputSource::Remote => false,
+            };
+            if is_local && (command == "verify" || command == "confirm") {
+                return usage_error(format!("{} only works with the puzzle inputs", command));
+            }
+            if is_local && days.len() != 1 {
+                return usage_error("--input requires exactly one day".to_owned());
+            }
+            let options = RunOptions {
+                days,
+                parts,
+                source,
+                format,
+                parallel,
+                jobs,
+            };
+            match command.as_str() {
+                "bench" => Command::Bench(options, iterations.unwrap_or(10)),
+                "verify" => Command::Verify(options),
+                "confirm" => Command::Confirm(options),
+                _ => Command::Run(options),
+            }
+        }
+    };
+    Ok(Invocation {
+        command,
+        network,
+        profiles,
+        timeout,
+        memory,
+    })
+}
+
+fn run_days(fw: &mut Framework, options: &RunOptions) -> Result<Vec<PartResult>> {
+    fw.set_output_format(options.format);
+    if options.parallel {
+        return fw.execute_parallel(
+            &options.days,
+            options.parts,
+            &options.source,
+            options.jobs,
+        );
+    }
+
+    let mut results = Vec::new();
+    for &day in &options.days {
+        results.extend(fw.execute(day, options.parts, &options.source)?);
+    }
+    Ok(results)
+}
+
+// Parts that timed out or panicked do not stop the other parts, but still fail
+// the command
+fn check_finished(results: &[PartResult]) -> Result<()> {
+    let unfinished = results.iter().filter(|result| result.answer.is_none()).count();
+    if unfinished > 0 {
+        return Err(Error::PartsFailed(unfinished));
+    }
+    Ok(())
+}
+
+pub fn execute(fw: &mut Framework, invocation: Invocation) -> Result<()> {
+    let network = invocation.network;
+    let mut fetcher = Fetcher::new(Client::new()).with_offline(network.offline);
+    if let Some(min_interval) = network.min_interval {
+        fetcher = fetcher.with_min_interval(min_interval);
+    }
+    if let Some(retries) = network.retries {
+        fetcher = fetcher.with_retries(retries, Duration::from_secs(1));
+    }
+    fw.set_fetcher(fetcher);
+    if let Some(token) = network.session {
+        fw.set_session_token(token);
+    }
+    if let Some(base_url) = network.base_url {
+        fw.set_base_url(base_url);
+    }
+    fw.set_timeout(invocation.timeout);
+    if invocation.memory {
+        memory::enable();
+    }
+
+    let profiles = invocation.profiles;
+    if let Command::Help = invocation.command {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    if profiles.len() == 1 {
+        fw.set_profile(&profiles[0])?;
+    }
+    if profiles.len() > 1 {
+        return execute_inputs(fw, invocation.command, &profiles);
+    }
+
+    match invocation.command {
+        Command::Run(options) => {
+            let results = run_days(fw, &options)?;
+            if options.days.len() > 1 && options.format == OutputFormat::Text {
+                framework::print_summary(&results);
+            }
+            check_finished(&results)?;
+        }
+        Command::Watch(options, interval) => {
+            fw.watch(options.days[0], options.parts, &options.source, interval)?;
+        }
+        Command::Bench(options, iterations) => {
+            fw.set_output_format(options.format);
+            let mut results = Vec::new();
+            for &day in &options.days {
+                results.extend(fw.bench(day, options.parts, &options.source, iterations)?);
+            }
+            if options.days.len() > 1 && options.format == OutputFormat::Text {
+                framework::print_bench_summary(&results);
+            }
+        }
+        Command::Verify(options) => {
+            let results = run_days(fw, &options)?;
+            if options.format == OutputFormat::Text {
+                framework::print_summary(&results);
+            }
+
+            let failures = results
+                .iter()
+                .filter(|result| result.status == Some(Status::Fail))
+                .count();
+            if failures > 0 {
+                return Err(Error::VerificationFailed(failures));
+            }
+            check_finished(&results)?;
+        }
+        Command::Confirm(options) => {
+            let results = run_days(fw, &options)?;
+            for result in &results {
+                fw.record_answer(result)?;
+            }
+            check_finished(&results)?;
+        }
+        Command::Examples(options) => {
+            fw.set_output_format(options.format);
+            let mut results = Vec::new();
+            for &day in &options.days {
+                results.extend(fw.run_examples(day, options.parts, &options.source)?);
+            }
+            if !results.is_empty() && options.format == OutputFormat::Text {
+                framework::print_summary(&results);
+            }
+
+            let failures = results
+                .iter()
+                .filter(|result| result.status == Some(Status::Fail))
+                .count();
+            if failures > 0 {
+                return Err(Error::VerificationFailed(failures));
+            }
+            check_finished(&results)?;
+        }
+        Command::ShowExamples(day, source) => fw.show_examples(day, &source)?,
+        Command::Debug(day, source) => {
+            let program = fw.read_input(day, &source)?.parse::<Program>()?;
+            let stdin = io::stdin();
+            debugger::debug(&program, stdin.lock(), &mut io::stdout().lock())?;
+        }
+        Command::Decompile(day, source) => {
+            let program = fw.read_input(day, &source)?.parse::<Program>()?;
+            print!("{}", decompiler::decompile(&program));
+        }
+        Command::Submit(day, parts, source) => {
+            fw.submit(day, parts, &source)?;
+        }
+        Command::Fetch(days) => {
+            for day in days {
+                fw.fetch(day)?;
+            }
+        }
+        Command::Cache(CacheCommand::List) => fw.list_cache()?,
+        Command::Cache(CacheCommand::Show(day)) => fw.show_cache(day)?,
+        Command::Cache(CacheCommand::Invalidate(days)) => {
+            for day in days {
+                fw.invalidate_cache(day)?;
+            }
+        }
+        Command::Cache(CacheCommand::Import(day, path)) => fw.import_cache(day, &path)?,
+        Command::Cache(CacheCommand::Clear) => fw.clear_cache()?,
+        Command::List => fw.list(),
+        Command::Help => println!("{}", USAGE),
+    }
+    Ok(())
+}
+
+fn run_inputs(
+    fw: &mut Framework,
+    options: &RunOptions,
+    profiles: &[String],
+) -> Result<Vec<MatrixCell>> {
+    fw.set_output_format(options.format);
+    let cells = fw.execute_inputs(&options.days, options.parts, &options.source, profiles)?;
+    if options.format == OutputFormat::Text {
+        framework::print_matrix(&cells);
+    }
+    Ok(cells)
+}
+
+fn check_cells(cells: &[MatrixCell]) -> Result<()> {
+    let failed = cells
+        .iter()
+        .filter(|cell| match cell.result {
+            Ok(ref result) => result.answer.is_none(),
+            Err(_) => true,
+        })
+        .count();
+    if failed > 0 {
+        return Err(Error::PartsFailed(failed));
+    }
+    Ok(())
+}
+
+fn record_cells(fw: &mut Framework, cells: &[MatrixCell]) -> Result<()> {
+    for cell in cells {
+        if let Ok(ref result) = cell.result {
+            fw.set_profile(&cell.input)?;
+            fw.record_answer(result)?;
+        }
+    }
+    Ok(())
+}
+
+// Runs the command once for every profile
+fn execute_inputs(fw: &mut Framework, command: Command, profiles: &[String]) -> Result<()> {
+    match command {
+        Command::Run(options) => {
+            check_cells(&run_inputs(fw, &options, profiles)?)?;
+        }
+        Command::Verify(options) => {
+            let cells = run_inputs(fw, &options, profiles)?;
+            let failures = cells
+                .iter()
+                .filter(|cell| match cell.result {
+                    Ok(ref result) => result.status == Some(Status::Fail),
+                    Err(_) => false,
+                })
+                .count();
+            if failures > 0 {
+                return Err(Error::VerificationFailed(failures));
+            }
+            check_cells(&cells)?;
+        }
+        Command::Confirm(options) => {
+            let cells = run_inputs(fw, &options, profiles)?;
+            // Answers that were found are kept even when other cells failed
+            let previous_profile = fw.profile_name().to_owned();
+            let recorded = record_cells(fw, &cells);
+            fw.set_profile(&previous_profile)?;
+            recorded?;
+            check_cells(&cells)?;
+        }
+        Command::Fetch(days) => {
+            for profile in profiles {
+                fw.set_profile(profile)?;
+                for &day in &days {
+                    fw.fetch(day)?;
+                }
+            }
+        }
+        _ => unreachable!("only commands that run days accept several profiles"),
+    }
+    Ok(())
+}
+
+pub fn print_usage() {
+    eprintln!("{}", USAGE);
+}
+
+#[test]
+fn cli_test() {
+    assert_eq!(parse_day_id("day05", Some(2018)).unwrap(), DayId::new(2018, 5));
+    assert_eq!(parse_day_id("5", Some(2018)).unwrap(), DayId::new(2018, 5));
+    assert_eq!(parse_day_id("2017/day25", Some(2018)).unwrap(), DayId::new(2017, 25));
+    assert_eq!(parse_day_id("2017/3", None).unwrap(), DayId::new(2017, 3));
+    assert!(parse_day_id("day05", None).is_err());
+    assert!(parse_day_id("dayfive", Some(2018)).is_err());
+    assert_eq!(DayId::new(2018, 5).to_string(), "2018/day05");
+    assert_eq!(
+        DayId::new(2018, 5).input_url(),
+        "https://adventofcode.com/2018/day/5/input"
+    );
+}
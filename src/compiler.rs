@@ -0,0 +1,446 @@
+// Fast execution of ElfCode programs. Instructions are decoded once into
+// operations on register indices, reads of the instruction pointer become
+// constants and writes to it become jumps, so the instruction pointer register
+// is only updated once the program halts. Loops with a known effect, like
+// summing divisors, are replaced by native code.
+use crate::decompiler::{self, Expr, Flow, Lifted, Term};
+use crate::elfcode::{Opcode, Operand, Program, Registers, Value};
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Register(usize),
+    Constant(Value),
+}
+
+impl Source {
+    fn get(self, registers: &[Value]) -> Value {
+        match self {
+            Source::Register(register) => registers[register],
+            Source::Constant(value) => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Compute(Opcode, Source, Source, usize),
+    Jump(Value),                      // to the address, past the end halts
+    Computed(Opcode, Source, Source), // to the value plus one
+    Idiom(Idiom),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Idiom {
+    // do { flag = factor * counter == target; if flag { sum += factor }
+    //      counter += 1; flag = counter > target } while !flag
+    SumOfDivisors {
+        factor: usize,
+        counter: usize,
+        target: usize,
+        sum: usize,
+        flag: usize,
+        exit: Value,
+    },
+    // loop { flag = (quotient + 1) * divisor > dividend; if flag { break }
+    //        quotient += 1 }
+    Divide {
+        quotient: usize,
+        divisor: Value,
+        dividend: usize,
+        flag: usize,
+        exit: Value,
+    },
+}
+
+impl Idiom {
+    // Whether the loop starting at `at` includes the instruction at `address`
+    fn covers(self, at: usize, address: usize) -> bool {
+        let len = match self {
+            Idiom::SumOfDivisors { .. } => 9,
+            Idiom::Divide { .. } => 8,
+        };
+        address > at && address < at + len
+    }
+
+    // Where execution continues, or None when the values are outside of the
+    // range the native code gives the same result for
+    fn apply(self, registers: &mut [Value]) -> Option<Value> {
+        match self {
+            Idiom::SumOfDivisors {
+                factor,
+                counter,
+                target,
+                sum,
+                flag,
+                exit,
+            } => {
+                let (a, first, n) = (registers[factor], registers[counter], registers[target]);
+                let last = first.max(n);
+                if a <= 0 || first < 0 {
+                    return None;
+                }
+                a.checked_mul(last)?;
+                let next = last.checked_add(1)?;
+                if n % a == 0 && n / a >= first && n / a <= last {
+                    registers[sum] = registers[sum].wrapping_add(a);
+                }
+                registers[counter] = next;
+                registers[flag] = 1;
+                Some(exit)
+            }
+            Idiom::Divide {
+                quotient,
+                divisor,
+                dividend,
+                flag,
+                exit,
+            } => {
+                let (q, d) = (registers[quotient], registers[dividend]);
+                if divisor <= 0 || q < 0 || d < 0 {
+                    return None;
+                }
+                let q = q.max(d / divisor);
+                (q.checked_add(1)?).checked_mul(divisor)?;
+                registers[quotient] = q;
+                registers[flag] = 1;
+                Some(exit)
+            }
+        }
+    }
+}
+
+fn register(lifted: &[Lifted], at: usize) -> Option<(Value, Expr)> {
+    lifted.get(at)?.assignment
+}
+
+fn jump(lifted: &[Lifted], at: usize) -> Option<Value> {
+    match lifted.get(at)?.flow {
+        Flow::Jump(target) => Some(target),
+        _ => None,
+    }
+}
+
+// The flag register and both targets of a branch
+fn branch(lifted: &[Lifted], at: usize) -> Option<(Value, Value, Value)> {
+    match lifted.get(at)?.flow {
+        Flow::Branch(condition, taken, skipped) => match condition.comparison.a {
+            Term::Register(flag) => Some((flag, taken, skipped)),
+            Term::Constant(_) => None,
+        },
+        _ => None,
+    }
+}
+
+// The registers both operands of `opcode` read, in either order
+fn operands(expr: Expr, opcode: Opcode) -> Option<(Value, Value)> {
+    match (expr.a, expr.b) {
+        (Term::Register(a), Term::Register(b)) if expr.opcode == opcode => Some((a, b)),
+        _ => None,
+    }
+}
+
+fn is_increment(expr: Expr, register: Value) -> bool {
+    expr.opcode == Opcode::Addi && expr.a == Term::Register(register) && expr.b == Term::Constant(1)
+}
+
+fn distinct(registers: &[Value]) -> bool {
+    (0..registers.len()).all(|i| !registers[i + 1..].contains(&registers[i]))
+}
+
+fn sum_of_divisors(lifted: &[Lifted], at: usize) -> Option<Idiom> {
+    let (flag, product) = register(lifted, at)?;
+    let (counter, increment) = register(lifted, at + 5)?;
+    let factor = match operands(product, Opcode::Mulr)? {
+        (factor, multiplied) if multiplied == counter => factor,
+        (multiplied, factor) if multiplied == counter => factor,
+        _ => return None,
+    };
+    let target = match register(lifted, at + 1)? {
+        (written, equal) if written == flag => match operands(equal, Opcode::Eqrr)? {
+            (a, target) | (target, a) if a == flag => target,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let (sum, add) = register(lifted, at + 4)?;
+    let added = operands(add, Opcode::Addr)?;
+    let base = at as Value;
+    let matches = branch(lifted, at + 2)? == (flag, base + 4, base + 3)
+        && jump(lifted, at + 3)? == base + 5
+        && (added == (factor, sum) || added == (sum, factor))
+        && is_increment(increment, counter)
+        && register(lifted, at + 6)? == (flag, greater(counter, target))
+        && branch(lifted, at + 7)? == (flag, base + 9, base + 8)
+        && jump(lifted, at + 8)? == base
+        && distinct(&[factor, counter, target, sum, flag]);
+    if !matches {
+        return None;
+    }
+    Some(Idiom::SumOfDivisors {
+        factor: factor as usize,
+        counter: counter as usize,
+        target: target as usize,
+        sum: sum as usize,
+        flag: flag as usize,
+        exit: base + 9,
+    })
+}
+
+fn greater(a: Value, b: Value) -> Expr {
+    Expr {
+        opcode: Opcode::Gtrr,
+        a: Term::Register(a),
+        b: Term::Register(b),
+    }
+}
+
+fn divide(lifted: &[Lifted], at: usize) -> Option<Idiom> {
+    let (flag, next) = register(lifted, at)?;
+    let quotient = match next.a {
+        Term::Register(quotient) if is_increment(next, quotient) => quotient,
+        _ => return None,
+    };
+    let divisor = match register(lifted, at + 1)? {
+        (written, product) if written == flag && product.opcode == Opcode::Muli => {
+            match (product.a, product.b) {
+                (Term::Register(a), Term::Constant(divisor)) if a == flag => divisor,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    let dividend = match register(lifted, at + 2)? {
+        (written, compare) if written == flag => match operands(compare, Opcode::Gtrr)? {
+            (a, dividend) if a == flag => dividend,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let (incremented, increment) = register(lifted, at + 6)?;
+    let exit = jump(lifted, at + 5)?;
+    let base = at as Value;
+    let matches = branch(lifted, at + 3)? == (flag, base + 5, base + 4)
+        && jump(lifted, at + 4)? == base + 6
+        && incremented == quotient
+        && is_increment(increment, quotient)
+        && jump(lifted, at + 7)? == base
+        && distinct(&[quotient, dividend, flag]);
+    if !matches {
+        return None;
+    }
+    Some(Idiom::Divide {
+        quotient: quotient as usize,
+        divisor,
+        dividend: dividend as usize,
+        flag: flag as usize,
+        exit,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compiled {
+    ops: Vec<Op>,
+    plain: Vec<Op>, // without idioms, for values the idioms do not handle
+    ip_register: Option<usize>,
+    registers: usize, // the number of registers the program uses
+}
+
+impl Compiled {
+    pub fn new(program: &Program) -> Result<Compiled> {
+        let mut registers = program.ip_register.map_or(0, |ip_register| ip_register + 1);
+        let mut source = |operand, value: Value, address: usize| match operand {
+            Operand::Register if value >= 0 && Some(value as usize) == program.ip_register => {
+                Ok(Source::Constant(address as Value))
+            }
+            Operand::Register if value >= 0 => {
+                registers = registers.max(value as usize + 1);
+                Ok(Source::Register(value as usize))
+            }
+            Operand::Register => Err(Error::Input("register out of range")),
+            Operand::Immediate | Operand::Ignored => Ok(Source::Constant(value)),
+        };
+
+        let mut plain = Vec::with_capacity(program.instructions.len());
+        for (address, instruction) in program.instructions.iter().enumerate() {
+            let (a, b) = instruction.opcode.operands();
+            let a = source(a, instruction.a, address)?;
+            let b = source(b, instruction.b, address)?;
+            let c = match source(Operand::Register, instruction.c, address)? {
+                Source::Register(c) => c,
+                Source::Constant(_) => {
+                    plain.push(match (a, b) {
+                        (Source::Constant(a), Source::Constant(b)) => {
+                            Op::Jump(instruction.opcode.apply(a, b).wrapping_add(1))
+                        }
+                        _ => Op::Computed(instruction.opcode, a, b),
+                    });
+                    continue;
+                }
+            };
+            plain.push(Op::Compute(instruction.opcode, a, b, c));
+        }
+
+        let lifted = decompiler::lift(program);
+        let ops = plain
+            .iter()
+            .enumerate()
+            .map(|(at, &op)| {
+                sum_of_divisors(&lifted, at)
+                    .or_else(|| divide(&lifted, at))
+                    .map_or(op, Op::Idiom)
+            })
+            .collect();
+        Ok(Compiled {
+            ops,
+            plain,
+            ip_register: program.ip_register,
+            registers,
+        })
+    }
+
+    // How many loops run as native code
+    pub fn idioms(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|op| match op {
+                Op::Idiom(_) => true,
+                _ => false,
+            })
+            .count()
+    }
+
+    // Runs the program until it halts, which leaves the registers like
+    // `Machine` does
+    pub fn run<R: Registers>(&self, registers: &mut R) -> Result<()> {
+        self.resume(registers, 0, None).map(|_| ())
+    }
+
+    // Runs from the instruction at `ip` until the program halts, or until it
+    // reaches the instruction at `stop` again. Returns where it stopped, None
+    // once it halted. The instruction pointer register is only up to date once
+    // the program halted.
+    pub fn resume<R: Registers>(
+        &self,
+        registers: &mut R,
+        ip: usize,
+        stop: Option<usize>,
+    ) -> Result<Option<usize>> {
+        let registers = registers.as_mut();
+        if registers.len() < self.registers {
+            return Err(Error::Input("register out of range"));
+        }
+
+        let mut ip = ip as Value;
+        let len = self.ops.len() as Value;
+        let mut first = true;
+        while ip >= 0 && ip < len {
+            let at = ip as usize;
+            if stop == Some(at) && !first {
+                return Ok(Some(at));
+            }
+            first = false;
+            let op = match self.ops[at] {
+                // Stopping inside of a loop means running every instruction
+                Op::Idiom(idiom) if stop.map_or(false, |stop| idiom.covers(at, stop)) => {
+                    self.plain[at]
+                }
+                Op::Idiom(idiom) => match idiom.apply(registers) {
+                    Some(next) => {
+                        ip = next;
+                        continue;
+                    }
+                    None => self.plain[at],
+                },
+                op => op,
+            };
+            ip = match op {
+                Op::Compute(opcode, a, b, c) => {
+                    registers[c] = opcode.apply(a.get(registers), b.get(registers));
+                    ip + 1
+                }
+                Op::Jump(target) => target,
+                Op::Computed(opcode, a, b) => opcode
+                    .apply(a.get(registers), b.get(registers))
+                    .wrapping_add(1),
+                Op::Idiom(_) => unreachable!("plain operations have no idioms"),
+            };
+        }
+        if let Some(ip_register) = self.ip_register {
+            registers[ip_register] = ip.wrapping_sub(1);
+        }
+        Ok(None)
+    }
+}
+
+#[test]
+fn compiler_test() {
+    use crate::elfcode::Machine;
+    use std::str::FromStr;
+
+    let check = |source: &str, idioms: usize, initial: [Value; 6]| {
+        let program = Program::from_str(source).unwrap();
+        let compiled = Compiled::new(&program).unwrap();
+        assert_eq!(compiled.idioms(), idioms);
+        let mut registers = initial;
+        compiled.run(&mut registers).unwrap();
+        let mut machine = Machine::<[Value; 6]>::new(&program);
+        machine.registers = initial;
+        machine.run().unwrap();
+        assert_eq!(registers, machine.registers);
+        registers
+    };
+
+    const SUM_OF_DIVISORS: &str = "#ip 3
+seti 1 0 4
+seti 1 7 2
+mulr 4 2 1
+eqrr 1 5 1
+addr 1 3 3
+addi 3 1 3
+addr 4 0 0
+addi 2 1 2
+gtrr 2 5 1
+addr 3 1 3
+seti 1 3 3
+addi 4 1 4
+gtrr 4 5 1
+addr 1 3 3
+seti 0 8 3
+mulr 3 3 3";
+    assert_eq!(check(SUM_OF_DIVISORS, 1, [0, 0, 0, 0, 0, 36])[0], 91);
+    check(SUM_OF_DIVISORS, 1, [0, 0, 0, 0, 0, 0]);
+    check(SUM_OF_DIVISORS, 1, [5, 0, 0, 0, 0, -3]);
+
+    const DIVIDE: &str = "#ip 1
+seti 0 7 4
+addi 4 1 3
+muli 3 256 3
+gtrr 3 5 3
+addr 3 1 1
+addi 1 1 1
+seti 8 0 1
+addi 4 1 4
+seti 0 0 1
+setr 4 0 0";
+    assert_eq!(check(DIVIDE, 1, [0, 0, 0, 0, 0, 65536])[0], 256);
+    check(DIVIDE, 1, [0, 0, 0, 0, 0, 255]);
+    check(DIVIDE, 1, [0, 0, 0, 0, 0, -1000]);
+
+    // Stopping inside of a loop runs it instruction by instruction
+    let compiled = Compiled::new(&Program::from_str(DIVIDE).unwrap()).unwrap();
+    let mut registers = [0, 0, 0, 0, 0, 1000];
+    let mut stops = 0;
+    let mut ip = 0;
+    while let Some(stop) = compiled.resume(&mut registers, ip, Some(7)).unwrap() {
+        stops += 1;
+        ip = stop;
+    }
+    assert_eq!((stops, registers[0]), (3, 3));
+
+    // Without #ip the instructions run in order
+    check("seti 7 0 1\nmulr 1 1 2\ngtir 50 2 3", 0, [0; 6]);
+    assert!(Compiled::new(&Program::from_str("addr -1 0 0").unwrap()).is_err());
+    let compiled = Compiled::new(&Program::from_str("addr 5 0 0").unwrap()).unwrap();
+    assert!(compiled.run(&mut [0; 4]).is_err());
+}
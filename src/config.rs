@@ -0,0 +1,161 @@
+// Session token and server settings from the config file, token.txt and the environment
+use crate::{Error, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const SESSION_ENV: &str = "AOC_SESSION";
+pub const BASE_URL_ENV: &str = "AOC_BASE_URL";
+const TOKEN_FILE: &str = "token.txt";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub session: Option<String>,
+    pub base_url: Option<String>,
+    pub profiles: BTreeMap<String, String>, // session tokens of other accounts, by name
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    ::dirs::config_dir().map(|dir| dir.join("advent_of_code_2018").join("config.json"))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_owned())
+    }
+}
+
+// Points a canonical adventofcode.com URL at another server
+pub fn rebase_url(url: &str, base_url: &str) -> String {
+    if url.starts_with(DEFAULT_BASE_URL) {
+        format!("{}{}", base_url.trim_end_matches('/'), &url[DEFAULT_BASE_URL.len()..])
+    } else {
+        url.to_owned()
+    }
+}
+
+impl Config {
+    pub fn from_json(value: &Value) -> Result<Config> {
+        let value = value
+            .as_object()
+            .ok_or(Error::Config("config must be a JSON object"))?;
+        let field = |name: &str| match value.get(name) {
+            Some(Value::String(s)) => Ok(non_empty(s)),
+            Some(Value::Null) | None => Ok(None),
+            Some(_) => Err(Error::Config("config values must be strings")),
+        };
+        let profiles = match value.get("profiles") {
+            Some(Value::Object(profiles)) => profiles
+                .iter()
+                .map(|(name, token)| match token.as_str().and_then(non_empty) {
+                    Some(token) => Ok((name.clone(), token)),
+                    None => Err(Error::Config("profile tokens must be non-empty strings")),
+                })
+                .collect::<Result<_>>()?,
+            Some(Value::Null) | None => BTreeMap::new(),
+            Some(_) => return Err(Error::Config("profiles must be a JSON object")),
+        };
+        Ok(Config {
+            session: field("session")?,
+            base_url: field("base_url")?,
+            profiles,
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Config> {
+        match fs::read(path) {
+            Ok(data) => Config::from_json(&::serde_json::from_slice(&data[..])?),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn from_env() -> Config {
+        let var = |name| env::var(name).ok().and_then(|value| non_empty(&value));
+        Config {
+            session: var(SESSION_ENV),
+            base_url: var(BASE_URL_ENV),
+            profiles: BTreeMap::new(),
+        }
+    }
+
+    // Values that are set in `other` take precedence
+    pub fn merge(mut self, other: Config) -> Config {
+        if other.session.is_some() {
+            self.session = other.session;
+        }
+        if other.base_url.is_some() {
+            self.base_url = other.base_url;
+        }
+        self.profiles.extend(other.profiles);
+        self
+    }
+
+    // From lowest to highest precedence: the config file, token.txt and the
+    // environment. Command line flags are applied on top of this.
+    pub fn load() -> Result<Config> {
+        let mut config = match config_path() {
+            Some(path) => Config::read(&path)?,
+            None => Config::default(),
+        };
+        if let Some(token) = fs::read_to_string(TOKEN_FILE)
+            .ok()
+            .and_then(|token| non_empty(&token))
+        {
+            config.session = Some(token);
+        }
+        Ok(config.merge(Config::from_env()))
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url
+            .as_ref()
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_BASE_URL)
+    }
+}
+
+#[test]
+fn config_test() {
+    use serde_json::json;
+
+    let file = Config::from_json(&json!({
+        "session": "abc\n",
+        "base_url": "http://localhost:8080/",
+        "profiles": { "alice": "ghi" },
+    }))
+    .unwrap();
+    assert_eq!(file.session, Some("abc".to_owned()));
+    assert_eq!(file.profiles["alice"], "ghi");
+    assert!(Config::from_json(&json!({ "profiles": { "bob": "" } })).is_err());
+    assert_eq!(file.base_url(), "http://localhost:8080/");
+    assert_eq!(Config::from_json(&json!({})).unwrap(), Config::default());
+    assert!(Config::from_json(&json!({ "session": 5 })).is_err());
+    assert!(Config::from_json(&json!([])).is_err());
+
+    let env = Config {
+        session: Some("def".to_owned()),
+        base_url: None,
+        profiles: BTreeMap::new(),
+    };
+    let merged = file.merge(env);
+    assert_eq!(merged.session, Some("def".to_owned()));
+    assert_eq!(merged.base_url, Some("http://localhost:8080/".to_owned()));
+    assert_eq!(merged.profiles.len(), 1);
+    assert_eq!(Config::default().base_url(), DEFAULT_BASE_URL);
+
+    let url = "https://adventofcode.com/2018/day/3/input";
+    assert_eq!(
+        rebase_url(url, "http://localhost:8080/"),
+        "http://localhost:8080/2018/day/3/input"
+    );
+    assert_eq!(rebase_url(url, DEFAULT_BASE_URL), url);
+    assert_eq!(rebase_url("http://other/x", "http://localhost"), "http://other/x");
+}
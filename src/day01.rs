@@ -1,38 +1,41 @@
 day!(
-    day01,
-    "https://adventofcode.com/2018/day/1/input",
+    2018,
+    1,
+    parse_input => Vec<isize>,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
 use regex::Regex;
 use std::collections::HashSet;
 
-fn parse_input<'a>(input: &'a str) -> impl Iterator<Item = Result<isize>> + 'a {
+pub fn parse_input(input: &str) -> Result<Vec<isize>> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r"(?P<s>\+|\-)(?P<v>\d+)").unwrap();
     };
     RE.captures_iter(input).map(|capture| {
-        let delta: isize = capture["v"].parse()?;
+        let at = Location::at_offset(input, capture.get(0).unwrap().start());
+        let delta: isize = capture["v"].parse::<isize>().map_err(Error::from).at(at)?;
         Ok(if &capture["s"] == "+" { delta } else { -delta })
     })
+    .collect()
 }
 
-fn part1(input: &str) -> Result<isize> {
-    let mut freq = 0;
-    for input in parse_input(input) {
-        freq += input?;
-    }
-    return Ok(freq);
+fn part1(deltas: &[isize]) -> Result<isize> {
+    Ok(deltas.iter().sum())
 }
 
-fn part2(input: &str) -> Result<isize> {
+fn part2(deltas: &[isize]) -> Result<isize> {
+    if deltas.is_empty() {
+        return Err(Error::Input("no frequency changes"));
+    }
     let mut seen = HashSet::new();
     let mut freq = 0;
     seen.insert(0);
     loop {
-        for input in parse_input(input) {
-            freq += input?;
+        for delta in deltas {
+            freq += delta;
             if !seen.insert(freq) {
                 return Ok(freq);
             }
@@ -1,10 +1,12 @@
 day!(
-    day02,
-    "https://adventofcode.com/2018/day/2/input",
+    2018,
+    2,
+    parse_ids => Vec<String>,
     part1,
     part2
 );
 
+use crate::error::ResultExt;
 use std::collections::HashMap;
 
 fn letter_counts(input: &str) -> HashMap<char, usize> {
@@ -15,10 +17,14 @@ fn letter_counts(input: &str) -> HashMap<char, usize> {
     map
 }
 
-fn part1(input: &str) -> Result<isize> {
+pub fn parse_ids(input: &str) -> Result<Vec<String>> {
+    Ok(input.lines().map(str::to_owned).collect())
+}
+
+fn part1(ids: &[String]) -> Result<isize> {
     let mut twos = 0;
     let mut threes = 0;
-    for count in input.lines().map(letter_counts) {
+    for count in ids.iter().map(|id| letter_counts(id)) {
         if count.iter().any(|(_, &value)| value == 2) {
             twos += 1;
         }
@@ -29,15 +35,13 @@ fn part1(input: &str) -> Result<isize> {
     return Ok(twos * threes);
 }
 
-fn part2(input: &str) -> Result<String> {
-    let input = input.lines().collect::<Vec<&str>>();
-
+fn part2(input: &[String]) -> Result<String> {
     if input.len() == 0 {
         return Err(Error::Input("empty input"));
     }
     let str_length = input[0].len();
-    if !input.iter().skip(1).all(|x| x.len() == str_length) {
-        return Err(Error::Input("not all strings have the same length"));
+    if let Some(i) = input.iter().position(|x| x.len() != str_length) {
+        return Err(Error::Input("not all strings have the same length")).at_line(i + 1, &input[i]);
     }
 
     for i in 0..input.len() - 1 {
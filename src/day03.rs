@@ -1,22 +1,24 @@
 day!(
-    day03,
-    "https://adventofcode.com/2018/day/3/input",
+    2018,
+    3,
+    parse_claims => Vec<Claim>,
     part1,
     part2
 );
 
+use crate::error::parse_lines;
 use regex::Regex;
 use smallvec::SmallVec;
 use std::collections::HashSet;
 use std::str::FromStr;
 
 #[derive(Debug, Clone)]
-struct Claim {
-    id: usize,
-    top: usize,
-    left: usize,
-    width: usize,
-    height: usize,
+pub struct Claim {
+    pub id: usize,
+    pub top: usize,
+    pub left: usize,
+    pub width: usize,
+    pub height: usize,
 }
 
 impl FromStr for Claim {
@@ -41,7 +43,7 @@ impl FromStr for Claim {
 }
 
 type RequestGrid = Vec<SmallVec<[usize; 4]>>;
-fn get_request_grid(claims: &Vec<Claim>) -> RequestGrid {
+fn get_request_grid(claims: &[Claim]) -> RequestGrid {
     let mut grid: RequestGrid = Vec::with_capacity(1000 * 1000);
     for _ in 0..1000 * 1000 {
         grid.push(SmallVec::new());
@@ -58,24 +60,18 @@ fn get_request_grid(claims: &Vec<Claim>) -> RequestGrid {
     grid
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let claims = input
-        .lines()
-        .map(Claim::from_str)
-        .collect::<Result<Vec<_>>>()?;
+pub fn parse_claims(input: &str) -> Result<Vec<Claim>> {
+    parse_lines(input, Claim::from_str)
+}
 
-    let req_grid = get_request_grid(&claims);
+fn part1(claims: &[Claim]) -> Result<usize> {
+    let req_grid = get_request_grid(claims);
     Ok(req_grid.iter().filter(|x| x.len() > 1).count())
 }
 
-fn part2(input: &str) -> Result<usize> {
-    let claims = input
-        .lines()
-        .map(Claim::from_str)
-        .collect::<Result<Vec<_>>>()?;
-
+fn part2(claims: &[Claim]) -> Result<usize> {
     let mut non_overlapping_ids = claims.iter().map(|x| x.id).collect::<HashSet<_>>();
-    let req_grid = get_request_grid(&claims);
+    let req_grid = get_request_grid(claims);
 
     for overlapping_cell in req_grid.iter().filter(|x| x.len() > 1) {
         for overlapping_id in overlapping_cell {
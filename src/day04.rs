@@ -1,10 +1,12 @@
 day!(
-    day04,
-    "https://adventofcode.com/2018/day/4/input",
+    2018,
+    4,
+    parse_schedule => HashMap<usize, Vec<TimeRange>>,
     part1,
     part2
 );
 
+use crate::error::parse_lines;
 use regex::Regex;
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -24,10 +26,10 @@ enum Action {
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
-struct TimeRange {
-    date: usize,
-    from: isize,
-    to: isize,
+pub struct TimeRange {
+    pub date: usize,
+    pub from: isize,
+    pub to: isize,
 }
 
 impl FromStr for Record {
@@ -94,10 +96,7 @@ impl FromStr for Action {
 }
 
 fn parse_input(input: &str) -> Result<Vec<Record>> {
-    input
-        .lines()
-        .map(Record::from_str)
-        .collect::<Result<Vec<Record>>>()
+    parse_lines(input, Record::from_str)
         .map(|mut records| {
             records.sort();
             records
@@ -191,10 +190,11 @@ fn calculate_most_asleep(ranges: &Vec<TimeRange>) -> (usize, usize) {
         .unwrap()
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let records = parse_input(input)?;
-    let time_ranges = transform(&records)?;
+pub fn parse_schedule(input: &str) -> Result<HashMap<usize, Vec<TimeRange>>> {
+    transform(&parse_input(input)?)
+}
 
+fn part1(time_ranges: &HashMap<usize, Vec<TimeRange>>) -> Result<usize> {
     time_ranges
         .iter()
         .map(|(&id, ranges)| {
@@ -215,10 +215,7 @@ fn part1(input: &str) -> Result<usize> {
         .ok_or(Error::Input("no records"))
 }
 
-fn part2(input: &str) -> Result<usize> {
-    let records = parse_input(input)?;
-    let time_ranges = transform(&records)?;
-
+fn part2(time_ranges: &HashMap<usize, Vec<TimeRange>>) -> Result<usize> {
     time_ranges
         .iter()
         .map(|(id, ranges)| {
@@ -1,18 +1,19 @@
 day!(
-    day05,
-    "https://adventofcode.com/2018/day/5/input",
+    2018,
+    5,
+    transform_input => Vec<u8>,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
 use smallvec::SmallVec;
 use std::collections::HashSet;
 
-fn transform_input(input: &str) -> Result<Vec<u8>> {
-    for &b in input.as_bytes() {
-        if b < 0x20 || b > 0x7f {
-            return Err(Error::Input("invalid characters in input"));
-        }
+pub fn transform_input(input: &str) -> Result<Vec<u8>> {
+    if let Some(offset) = input.bytes().position(|b| b < 0x20 || b > 0x7f) {
+        let at = Location::at_offset(input, offset);
+        return Err(Error::Input("invalid characters in input")).at(at);
     }
     Ok(input.bytes().rev().collect())
 }
@@ -123,14 +124,13 @@ fn react(data: &mut Vec<u8>) -> bool {
     }
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let mut data = transform_input(input)?;
+fn part1(data: &[u8]) -> Result<usize> {
+    let mut data = data.to_vec();
     react_fully(&mut data);
     Ok(data.len())
 }
 
-fn part2(input: &str) -> Result<usize> {
-    let data = transform_input(input)?;
+fn part2(data: &[u8]) -> Result<usize> {
     data.iter()
         .map(|&c| (c as char).to_ascii_lowercase())
         .collect::<HashSet<char>>()
@@ -138,7 +138,7 @@ fn part2(input: &str) -> Result<usize> {
         .map(|lower| {
             let upper = lower.to_ascii_uppercase() as u8;
             let lower = lower as u8;
-            let mut data = data.clone();
+            let mut data = data.to_vec();
             data.drain_filter(|&mut x| x == lower || x == upper);
             react_fully(&mut data);
             data.len()
@@ -1,15 +1,17 @@
 day!(
-    day06,
-    "https://adventofcode.com/2018/day/6/input",
+    2018,
+    6,
+    parse_input => Vec<Vec2i>,
     part1,
     part2
 );
 
+use crate::error::parse_lines;
 use crate::vec2::{Vec2i, AabbIteratorEx};
 use smallvec::SmallVec;
 
-fn parse_input(input: &str) -> Result<Vec<Vec2i>> {
-    input.lines().map(|x| Ok(x.parse()?)).collect()
+pub fn parse_input(input: &str) -> Result<Vec<Vec2i>> {
+    parse_lines(input, |line| Ok(line.parse()?))
 }
 
 #[derive(Debug)]
@@ -17,7 +19,7 @@ struct Point {
     pos: Vec2i,
     closest_to_count: usize,
 }
-fn compute_grid(points: &Vec<Vec2i>, padding: i32) -> Vec<Point> {
+fn compute_grid(points: &[Vec2i], padding: i32) -> Vec<Point> {
     fn find_closest_point(points: &Vec<Point>, pos: Vec2i) -> SmallVec<[usize; 4]> {
         let point_dists: SmallVec<[i32; 64]> = points
             .iter()
@@ -62,11 +64,9 @@ fn compute_grid(points: &Vec<Vec2i>, padding: i32) -> Vec<Point> {
     points
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let input = parse_input(input)?;
-
-    let base = compute_grid(&input, 0);
-    let padded = compute_grid(&input, 1);
+fn part1(points: &[Vec2i]) -> Result<usize> {
+    let base = compute_grid(points, 0);
+    let padded = compute_grid(points, 1);
 
     base.into_iter()
         .zip(padded.into_iter())
@@ -82,9 +82,7 @@ fn part1(input: &str) -> Result<usize> {
         .ok_or(Error::Input("no non-infinite points"))
 }
 
-fn part2_impl(input: &str, max_distance: i32) -> Result<usize> {
-    let points = parse_input(input)?;
-
+fn part2_impl(points: &[Vec2i], max_distance: i32) -> Result<usize> {
     let min = Vec2i::new(
         points.iter().map(|v| v.x).min().unwrap(),
         points.iter().map(|v| v.y).min().unwrap(),
@@ -110,8 +108,8 @@ fn part2_impl(input: &str, max_distance: i32) -> Result<usize> {
     Ok(in_range)
 }
 
-fn part2(input: &str) -> Result<usize> {
-    part2_impl(input, 10000)
+fn part2(points: &[Vec2i]) -> Result<usize> {
+    part2_impl(points, 10000)
 }
 
 #[test]
@@ -123,8 +121,8 @@ fn day06_test() {
 5, 5
 8, 9";
 
-    fn part2_test(input: &str) -> Result<usize> {
-        part2_impl(input, 32)
+    fn part2_test(points: &[Vec2i]) -> Result<usize> {
+        part2_impl(points, 32)
     }
 
     assert_eq!(
@@ -1,10 +1,12 @@
 day!(
-    day07,
-    "https://adventofcode.com/2018/day/7/input",
+    2018,
+    7,
+    create_dependency_graph => HashMap<char, Vec<char>>,
     part1,
     part2
 );
 
+use crate::error::parse_lines;
 use regex::Regex;
 use smallvec::SmallVec;
 use std::collections::{HashMap, HashSet};
@@ -34,11 +36,8 @@ impl FromStr for Rule {
     }
 }
 
-fn create_dependency_graph(input: &str) -> Result<HashMap<char, Vec<char>>> {
-    let rules = input
-        .lines()
-        .map(Rule::from_str)
-        .collect::<Result<Vec<_>>>()?;
+pub fn create_dependency_graph(input: &str) -> Result<HashMap<char, Vec<char>>> {
+    let rules = parse_lines(input, Rule::from_str)?;
 
     let mut dependencies: HashMap<char, Vec<char>> = HashMap::new();
     for rule in rules {
@@ -48,8 +47,8 @@ fn create_dependency_graph(input: &str) -> Result<HashMap<char, Vec<char>>> {
     Ok(dependencies)
 }
 
-fn part1(input: &str) -> Result<String> {
-    let mut dependencies = create_dependency_graph(input)?;
+fn part1(dependencies: &HashMap<char, Vec<char>>) -> Result<String> {
+    let mut dependencies = dependencies.clone();
 
     let mut order = String::with_capacity(dependencies.len());
     while !dependencies.is_empty() {
@@ -66,8 +65,12 @@ fn part1(input: &str) -> Result<String> {
     Ok(order)
 }
 
-fn part2_impl(input: &str, extra_time: usize, worker_count: usize) -> Result<usize> {
-    let mut dependencies = create_dependency_graph(input)?;
+fn part2_impl(
+    dependencies: &HashMap<char, Vec<char>>,
+    extra_time: usize,
+    worker_count: usize,
+) -> Result<usize> {
+    let mut dependencies = dependencies.clone();
     let mut in_progress = HashSet::new();
 
     #[derive(Debug, Clone)]
@@ -122,8 +125,8 @@ fn part2_impl(input: &str, extra_time: usize, worker_count: usize) -> Result<usi
     Ok(time)
 }
 
-fn part2(input: &str) -> Result<usize> {
-    part2_impl(input, 60, 5)
+fn part2(dependencies: &HashMap<char, Vec<char>>) -> Result<usize> {
+    part2_impl(dependencies, 60, 5)
 }
 
 #[test]
@@ -136,8 +139,8 @@ Step B must be finished before step E can begin.
 Step D must be finished before step E can begin.
 Step F must be finished before step E can begin.";
 
-    fn part2_test(input: &str) -> Result<usize> {
-        part2_impl(input, 0, 2)
+    fn part2_test(dependencies: &HashMap<char, Vec<char>>) -> Result<usize> {
+        part2_impl(dependencies, 0, 2)
     }
 
     assert_results!(part1, EXAMPLE => "CABDFE");
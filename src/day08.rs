@@ -1,21 +1,31 @@
 day!(
-    day08,
-    "https://adventofcode.com/2018/day/8/input",
+    2018,
+    8,
+    parse_nodes => Node,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
+use regex::Regex;
+
 #[derive(Debug, Clone)]
-struct Node {
-    node_index: usize,
-    children: Vec<Node>,
-    metadata: Vec<usize>,
+pub struct Node {
+    pub node_index: usize,
+    pub children: Vec<Node>,
+    pub metadata: Vec<usize>,
 }
 
-fn parse_nodes(input: &str) -> Result<Node> {
-    let nrs = input
-        .split(' ')
-        .map(|s| Ok(s.parse()?))
+pub fn parse_nodes(input: &str) -> Result<Node> {
+    lazy_static! {
+        static ref NUMBER: Regex = Regex::new(r"\S+").unwrap();
+    }
+    let nrs = NUMBER
+        .find_iter(input)
+        .map(|m| {
+            let nr = m.as_str().parse().map_err(Error::from);
+            nr.at(Location::at_offset(input, m.start()))
+        })
         .collect::<Result<Vec<_>>>()?;
 
     let mut node_index = 0;
@@ -35,22 +45,20 @@ fn parse_nodes(input: &str) -> Result<Node> {
         })
     }
 
-    parse_node(&mut nrs.into_iter(), &mut node_index)
+    // Only running out of numbers fails, which happens at the end of the input
+    parse_node(&mut nrs.into_iter(), &mut node_index).at(Location::at_offset(input, input.len()))
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let root = parse_nodes(input)?;
+fn part1(root: &Node) -> Result<usize> {
     fn visit(node: &Node) -> usize {
         node.metadata.iter().cloned().sum::<usize>()
             + node.children.iter().map(visit).sum::<usize>()
     }
 
-    Ok(visit(&root))
+    Ok(visit(root))
 }
 
-fn part2(input: &str) -> Result<usize> {
-    let root = parse_nodes(input)?;
-
+fn part2(root: &Node) -> Result<usize> {
     fn visit(node: &Node) -> usize {
         if node.children.len() == 0 {
             node.metadata.iter().cloned().sum::<usize>()
@@ -63,7 +71,7 @@ fn part2(input: &str) -> Result<usize> {
         }
     }
 
-    Ok(visit(&root))
+    Ok(visit(root))
 }
 
 #[test]
@@ -1,18 +1,20 @@
 day!(
-    day09,
-    "https://adventofcode.com/2018/day/9/input",
+    2018,
+    9,
+    Counts::from_str => Counts,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
 use regex::Regex;
 use std::collections::VecDeque;
 use std::str::FromStr;
 
 #[derive(Debug, Clone)]
-struct Counts {
-    player: usize,
-    marble: usize,
+pub struct Counts {
+    pub player: usize,
+    pub marble: usize,
 }
 
 impl FromStr for Counts {
@@ -25,18 +27,19 @@ impl FromStr for Counts {
         }
         let c = RE
             .captures(s)
-            .ok_or(Error::Input("invalid puzzle format"))?;
-        let player = c["p"].parse::<usize>().unwrap();
-        let marble = c["m"].parse::<usize>().unwrap() + 1;
+            .ok_or(Error::Input("invalid puzzle format"))
+            .at_line(1, s)?;
+        let at = |name| Location::at_offset(s, c.name(name).unwrap().start());
+        let player = c["p"].parse::<usize>().map_err(Error::from).at(at("p"))?;
+        let marble = c["m"].parse::<usize>().map_err(Error::from).at(at("m"))? + 1;
         if player == 0 {
-            return Err(Error::Input("player count may not be zero"));
+            return Err(Error::Input("player count may not be zero")).at(at("p"));
         }
         Ok(Counts { player, marble })
     }
 }
 
-fn part1(input: &str) -> Result<u64> {
-    let counts: Counts = input.parse()?;
+fn part1(counts: &Counts) -> Result<u64> {
     let mut player_scores = vec![0u64; counts.player];
     let mut marble_ring = VecDeque::with_capacity(counts.marble);
     marble_ring.push_back(0);
@@ -63,8 +66,8 @@ fn part1(input: &str) -> Result<u64> {
     Ok(player_scores.into_iter().max().unwrap())
 }
 
-fn part2_impl(input: &str, should_multiply_input: bool) -> Result<u64> {
-    let mut counts: Counts = input.parse()?;
+fn part2_impl(counts: &Counts, should_multiply_input: bool) -> Result<u64> {
+    let mut counts = counts.clone();
     if should_multiply_input {
         counts.marble = (counts.marble - 1) * 100 + 1;
     }
@@ -125,15 +128,15 @@ fn part2_impl(input: &str, should_multiply_input: bool) -> Result<u64> {
     Ok(player_scores.into_iter().max().unwrap())
 }
 
-fn part2(input: &str) -> Result<u64> {
-    part2_impl(input, true)
+fn part2(counts: &Counts) -> Result<u64> {
+    part2_impl(counts, true)
 }
 
 #[test]
 fn day09_test() {
-    fn test_both(input: &str) -> Result<u64> {
-        let res1 = part1(input).unwrap();
-        let res2 = part2_impl(input, false).unwrap();
+    fn test_both(counts: &Counts) -> Result<u64> {
+        let res1 = part1(counts).unwrap();
+        let res2 = part2_impl(counts, false).unwrap();
         assert_eq!(res1, res2);
         Ok(res1)
     }
@@ -1,18 +1,21 @@
 day!(
-    day10,
-    "https://adventofcode.com/2018/day/10/input",
+    2018,
+    10,
+    parse_sky => Sky,
     part1,
     part2
 );
 
+use crate::error::parse_lines;
 use crate::vec2::{AabbIteratorEx, Vec2i};
 use regex::Regex;
 use std::str::FromStr;
+use std::sync::Mutex;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
-struct Point {
-    position: Vec2i,
-    velocity: Vec2i,
+pub struct Point {
+    pub position: Vec2i,
+    pub velocity: Vec2i,
 }
 
 impl FromStr for Point {
@@ -53,11 +56,48 @@ fn points_to_str(points: &[Point]) -> String {
     String::from_utf8(res).unwrap()
 }
 
-fn part_both(input: &str) -> Result<(usize, String)> {
-    let mut points_fore = input
-        .lines()
-        .map(Point::from_str)
-        .collect::<Result<Vec<Point>>>()?;
+pub fn parse_points(input: &str) -> Result<Vec<Point>> {
+    parse_lines(input, Point::from_str)
+}
+
+// The moment the message appears, part 1 reads its text and part 2 its time
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Message {
+    pub time: usize,
+    pub text: String,
+}
+
+#[derive(Debug)]
+pub struct Sky {
+    pub points: Vec<Point>,
+    pub message: Mutex<Option<Message>>, // found by whichever part runs first
+}
+
+impl Sky {
+    pub fn new(points: Vec<Point>) -> Sky {
+        Sky {
+            points,
+            message: Mutex::new(None),
+        }
+    }
+
+    // Simulating is the expensive part, so it only happens once, and counts
+    // towards the part that needs the message first
+    pub fn message(&self) -> Result<Message> {
+        let mut message = self.message.lock().unwrap_or_else(|e| e.into_inner());
+        if message.is_none() {
+            *message = Some(find_message(&self.points)?);
+        }
+        Ok(message.clone().unwrap())
+    }
+}
+
+pub fn parse_sky(input: &str) -> Result<Sky> {
+    Ok(Sky::new(parse_points(input)?))
+}
+
+fn find_message(points: &[Point]) -> Result<Message> {
+    let mut points_fore = points.to_vec();
     let mut points_back = points_fore.clone();
 
     let mut last_aabb_size = {
@@ -85,7 +125,10 @@ fn part_both(input: &str) -> Result<(usize, String)> {
 
         // Check if the new AABB is larger, and if so, return the previous result
         if current_aabb_size.x + current_aabb_size.y > last_aabb_size.x + last_aabb_size.y {
-            return Ok((time, points_to_str(&points_fore)));
+            return Ok(Message {
+                time,
+                text: points_to_str(&points_fore),
+            });
         }
 
         // Store and swap
@@ -96,12 +139,12 @@ fn part_both(input: &str) -> Result<(usize, String)> {
     Err(Error::Input("cannot solve input in a million iterations"))
 }
 
-fn part1(input: &str) -> Result<String> {
-    part_both(input).map(|(_, s)| s)
+fn part1(sky: &Sky) -> Result<String> {
+    Ok(sky.message()?.text)
 }
 
-fn part2(input: &str) -> Result<usize> {
-    part_both(input).map(|(t, _)| t)
+fn part2(sky: &Sky) -> Result<usize> {
+    Ok(sky.message()?.time)
 }
 
 #[test]
@@ -174,13 +217,7 @@ position=<-3,  6> velocity=< 2, -1>";
 
     assert_eq!(
         INITIAL,
-        points_to_str(
-            &EXAMPLE
-                .lines()
-                .map(Point::from_str)
-                .collect::<Result<Vec<_>>>()
-                .unwrap()
-        )
+        points_to_str(&parse_points(EXAMPLE).unwrap())
     );
 
     assert_results!(part1, EXAMPLE => OUTPUT);
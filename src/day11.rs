@@ -1,11 +1,13 @@
 day!(
-    day11,
-    "https://adventofcode.com/2018/day/11/input",
+    2018,
+    11,
+    parse_serial_number => i32,
     part1,
     part2
 );
 
 use crate::vec2::{Vec2i, Vec2us};
+use crate::vec3::Vec3us;
 use crate::mat2::Mat2;
 
 const SIZE: usize = 300;
@@ -29,8 +31,11 @@ fn make_grid(serial_number: i32) -> Mat2<i8> {
     res
 }
 
-fn part1(input: &str) -> Result<Vec2i> {
-    let serial_number: i32 = input.parse()?;
+pub fn parse_serial_number(input: &str) -> Result<i32> {
+    Ok(input.parse()?)
+}
+
+fn part1(&serial_number: &i32) -> Result<Vec2i> {
     let grid = make_grid(serial_number);
 
     let mut sums = Vec::with_capacity((SIZE - 2) * (SIZE - 2));
@@ -53,8 +58,7 @@ fn part1(input: &str) -> Result<Vec2i> {
         .ok_or(Error::Input("no maximum value"))
 }
 
-fn part2(input: &str) -> Result<String> {
-    let serial_number: i32 = input.parse()?;
+fn part2(&serial_number: &i32) -> Result<Vec3us> {
     let grid = make_grid(serial_number);
     let mut integral_grid = Mat2::new(0i32, Vec2us::from(SIZE));
 
@@ -104,7 +108,7 @@ fn part2(input: &str) -> Result<String> {
             (size, xmin, ymin, sum)
         })
         .max_by_key(|(_, _, _, sum)| *sum)
-        .map(|(size, x, y, _)| format!("{},{},{}", x + 1, y + 1, size))
+        .map(|(size, x, y, _)| Vec3us::new(x + 1, y + 1, size))
         .ok_or(Error::Input("unsolveable"))
 }
 
@@ -120,7 +124,7 @@ fn day11_test() {
     );
 
     assert_results!(part2,
-        "18" => "90,269,16",
-        "42" => "232,251,12",
+        "18" => Vec3us::new(90, 269, 16),
+        "42" => Vec3us::new(232, 251, 12),
     );
 }
@@ -1,20 +1,23 @@
 day!(
-    day12,
-    "https://adventofcode.com/2018/day/12/input",
+    2018,
+    12,
+    parse_input => (State, Transformations),
     part1,
-    part2
+    part2,
+    examples: [part1(0 => 0)]
 );
 
+use crate::error::ResultExt;
 use smallvec::SmallVec;
 use std::collections::{HashMap, VecDeque};
 use std::iter;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct State {
-    has_plants: Vec<bool>,
-    index_offset: i64,
+pub struct State {
+    pub has_plants: Vec<bool>,
+    pub index_offset: i64,
 }
-type Transformations = [bool; 32];
+pub type Transformations = [bool; 32];
 
 #[rustfmt::skip]
 fn bools_to_nr(bools: [bool; 5]) -> u8 {
@@ -27,49 +30,57 @@ fn bools_to_nr(bools: [bool; 5]) -> u8 {
     (n(bools[4]))
 }
 
-fn parse_input(input: &str) -> Result<(State, Transformations)> {
-    fn char_to_bool(c: char) -> Result<bool> {
-        match c {
-            '#' => Ok(true),
-            '.' => Ok(false),
-            _ => Err(Error::Input("invalid chars in initial state")),
-        }
+fn char_to_bool(c: char) -> Result<bool> {
+    match c {
+        '#' => Ok(true),
+        '.' => Ok(false),
+        _ => Err(Error::Input("invalid chars in initial state")),
     }
+}
 
-    let mut lines = input.lines();
-    let mut initial_state = lines.next().ok_or(Error::Input("empty input"))?;
-    if !initial_state.starts_with("initial state: ") {
+fn parse_state(line: &str) -> Result<State> {
+    if !line.starts_with("initial state: ") {
         return Err(Error::Input("expected initial state"));
     }
-    initial_state = &initial_state[15..];
-    let state = State {
-        has_plants: initial_state
-            .chars()
-            .map(char_to_bool)
-            .collect::<Result<_>>()?,
+    Ok(State {
+        has_plants: line[15..].chars().map(char_to_bool).collect::<Result<_>>()?,
         index_offset: 0,
-    };
+    })
+}
 
-    if lines.next() != Some("") {
-        return Err(Error::Input(
-            "expected empty line between initial state and transformations",
-        ));
+fn parse_transformation(line: &str) -> Result<([bool; 5], bool)> {
+    let line = line.as_bytes();
+    if line.len() != 10 || !line.iter().all(|b| *b < 128) || &line[5..9] != b" => " {
+        return Err(Error::Input("invalid transformation"));
     }
+    let input = [
+        char_to_bool(line[0] as char)?,
+        char_to_bool(line[1] as char)?,
+        char_to_bool(line[2] as char)?,
+        char_to_bool(line[3] as char)?,
+        char_to_bool(line[4] as char)?,
+    ];
+    Ok((input, char_to_bool(line[9] as char)?))
+}
 
-    let mut transformations: Transformations = [false; 32];
-    while let Some(line) = lines.next() {
-        let line = line.as_bytes();
-        if line.len() != 10 || !line.iter().all(|b| *b < 128) || &line[5..9] != b" => " {
-            return Err(Error::Input("invalid transformation"));
+pub fn parse_input(input: &str) -> Result<(State, Transformations)> {
+    let mut lines = input.lines();
+    let first = lines.next().ok_or(Error::Input("empty input"))?;
+    let state = parse_state(first).at_line(1, first)?;
+
+    match lines.next() {
+        Some("") => {}
+        second => {
+            return Err(Error::Input(
+                "expected empty line between initial state and transformations",
+            ))
+            .at_line(2, second.unwrap_or(""));
         }
-        let input = [
-            char_to_bool(line[0] as char)?,
-            char_to_bool(line[1] as char)?,
-            char_to_bool(line[2] as char)?,
-            char_to_bool(line[3] as char)?,
-            char_to_bool(line[4] as char)?,
-        ];
-        let output = char_to_bool(line[9] as char)?;
+    }
+
+    let mut transformations: Transformations = [false; 32];
+    for (i, line) in lines.enumerate() {
+        let (input, output) = parse_transformation(line).at_line(i + 3, line)?;
         transformations[bools_to_nr(input) as usize] = output;
     }
 
@@ -129,9 +140,8 @@ fn sum_state(state: &State) -> i64 {
         .sum()
 }
 
-fn part1(input: &str) -> Result<i64> {
-    let (state, transformations) = parse_input(input)?;
-    let state = process_n(20, state, &transformations);
+fn part1((state, transformations): &(State, Transformations)) -> Result<i64> {
+    let state = process_n(20, state.clone(), transformations);
 
     Ok(sum_state(&state))
 }
@@ -153,12 +163,11 @@ fn compress_bools(vec: &VecDeque<bool>) -> SmallVec<[u64; 4]> {
     res
 }
 
-fn part2(input: &str) -> Result<i64> {
+fn part2((state, transformations): &(State, Transformations)) -> Result<i64> {
     const TARGET_ITERATIONS: u64 = 50000000000;
-    let (state, transformations) = parse_input(input)?;
 
     let mut index_offset = state.index_offset;
-    let mut fore_buffer: VecDeque<bool> = state.has_plants.into_iter().collect();
+    let mut fore_buffer: VecDeque<bool> = state.has_plants.iter().cloned().collect();
     fn trim(vec: &mut VecDeque<bool>, index_offset: &mut i64) {
         while let Some(&false) = vec.front() {
             vec.pop_front();
@@ -215,7 +224,7 @@ fn part2(input: &str) -> Result<i64> {
                 index_offset: index_offset
                     + (index_offset - previous_index_offset) * (full_cycles_left as i64),
             };
-            let state = process_n(remaining_iterations as usize, state, &transformations);
+            let state = process_n(remaining_iterations as usize, state, transformations);
             return Ok(sum_state(&state));
         }
     }
@@ -243,4 +252,5 @@ fn day12_test() {
 ####. => #";
 
     assert_results!(part1, EXAMPLE => 325);
+    assert!(parse_input("initial state: #..#").is_err());
 }
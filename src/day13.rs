@@ -1,10 +1,12 @@
 day!(
-    day13,
-    "https://adventofcode.com/2018/day/13/input",
+    2018,
+    13,
+    Board::from_str => Board,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
 use crate::vec2::Vec2us;
 use smallvec::SmallVec;
 use std::collections::HashMap;
@@ -37,7 +39,7 @@ struct Cart {
 }
 
 #[derive(Debug, Clone)]
-struct Board {
+pub struct Board {
     layout: HashMap<Vec2us, Cell>,
     carts: Vec<Cart>,
 }
@@ -85,7 +87,8 @@ impl FromStr for Board {
                     'v' => (Direction::YPos, Cell::Vertical),
                     ' ' => continue,
                     _ => {
-                        layout.insert(position, Cell::try_from(c)?);
+                        let cell = Cell::try_from(c).at(Location::cell(y + 1, x + 1, line))?;
+                        layout.insert(position, cell);
                         continue;
                     }
                 };
@@ -211,8 +214,8 @@ impl Board {
     }
 }
 
-fn part1(input: &str) -> Result<Vec2us> {
-    let mut board: Board = input.parse()?;
+fn part1(board: &Board) -> Result<Vec2us> {
+    let mut board = board.clone();
     for _ in 0..1_000_000 {
         let new_crashes = board.tick();
         if !new_crashes.is_empty() {
@@ -222,8 +225,8 @@ fn part1(input: &str) -> Result<Vec2us> {
     Err(Error::Input("cannot solve in a million iterations"))
 }
 
-fn part2(input: &str) -> Result<Vec2us> {
-    let mut board: Board = input.parse()?;
+fn part2(board: &Board) -> Result<Vec2us> {
+    let mut board = board.clone();
     for _ in 0..1_000_000 {
         let _ = board.tick();
         if board.carts.len() == 1 {
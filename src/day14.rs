@@ -1,10 +1,12 @@
 day!(
-    day14,
-    "https://adventofcode.com/2018/day/14/input",
+    2018,
+    14,
+    parse_digits => Vec<u8>,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
 use twoway::find_bytes;
 
 #[derive(Debug, Clone)]
@@ -42,8 +44,30 @@ impl State {
     }
 }
 
-fn part1(input: &str) -> Result<String> {
-    let input = input.parse::<usize>()?;
+// Part 1 reads the input as a number, part 2 as a sequence of digits
+pub fn parse_digits(input: &str) -> Result<Vec<u8>> {
+    if input.is_empty() {
+        return Err(Error::Input("expected digits as input"));
+    }
+    input
+        .bytes()
+        .enumerate()
+        .map(|(offset, byte)| match byte {
+            b'0'..=b'9' => Ok(byte - b'0'),
+            _ => {
+                let at = Location::at_offset(input, offset);
+                Err(Error::Input("expected digits as input")).at(at)
+            }
+        })
+        .collect()
+}
+
+fn part1(digits: &[u8]) -> Result<String> {
+    let input = digits
+        .iter()
+        .try_fold(0usize, |n, &digit| n.checked_mul(10)?.checked_add(digit as usize))
+        .filter(|n| n.checked_add(10).is_some())
+        .ok_or(Error::Input("number of recipes is too large"))?;
     let mut state = State::new();
 
     loop {
@@ -60,16 +84,8 @@ fn part1(input: &str) -> Result<String> {
     }
     Ok(res)
 }
-fn part2(input: &str) -> Result<usize> {
-    let length = input.len();
-    let mut search_target = Vec::with_capacity(length);
-    for i in 0..length {
-        let byte = input.as_bytes()[i];
-        if byte < b'0' || byte > b'9' {
-            return Err(Error::Input("expected digits as input"));
-        }
-        search_target.push(byte - b'0');
-    }
+fn part2(search_target: &[u8]) -> Result<usize> {
+    let length = search_target.len();
 
     let mut state = State::new();
     while state.recipes.len() < length {
@@ -80,7 +96,7 @@ fn part2(input: &str) -> Result<usize> {
     for _ in 0..100_000_000 {
         state.progress();
 
-        if let Some(offset) = find_bytes(&state.recipes[checked_from_index..], &search_target) {
+        if let Some(offset) = find_bytes(&state.recipes[checked_from_index..], search_target) {
             return Ok(offset + checked_from_index);
         }
         checked_from_index = state.recipes.len() + 1 - length;
@@ -103,4 +119,6 @@ fn day14_test() {
         "92510" => 18,
         "59414" => 2018,
     );
+    assert!(parse_digits("").is_err());
+    assert!(part1(&parse_digits("99999999999999999999999").unwrap()).is_err());
 }
@@ -1,10 +1,12 @@
 day!(
-    day15,
-    "https://adventofcode.com/2018/day/15/input",
+    2018,
+    15,
+    Grid::from_str => Grid,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
 use crate::mat2::Mat2;
 use crate::vec2::Vec2us;
 use pathfinding::directed::astar::astar;
@@ -34,7 +36,7 @@ enum UnitKind {
 }
 
 #[derive(Clone)]
-struct Grid {
+pub struct Grid {
     cells: Mat2<Cell>,
     units: Vec<Unit>,
 }
@@ -105,10 +107,9 @@ impl FromStr for Grid {
     type Err = Error;
     fn from_str(s: &str) -> Result<Grid> {
         let size = {
-            let mut lines = s.lines();
-            let width = lines.next().ok_or(Error::Input("empty input"))?.len();
-            if !lines.all(|x| x.len() == width) {
-                return Err(Error::Input("inconsistent line width"));
+            let width = s.lines().next().ok_or(Error::Input("empty input"))?.len();
+            if let Some((y, line)) = s.lines().enumerate().find(|(_, x)| x.len() != width) {
+                return Err(Error::Input("inconsistent line width")).at_line(y + 1, line);
             }
             let height = s.lines().count();
             Vec2us::new(width, height)
@@ -137,7 +138,8 @@ impl FromStr for Grid {
                         }
                     }
                     _ => {
-                        return Err(Error::Input("invalid character in input"));
+                        return Err(Error::Input("invalid character in input"))
+                            .at(Location::cell(y + 1, x + 1, line));
                     }
                 };
             }
@@ -465,26 +467,20 @@ fn reading_order(a: &Vec2us, b: &Vec2us) -> Ordering {
     a.y.cmp(&b.y).then_with(|| a.x.cmp(&b.x))
 }
 
-fn part1(input: &str) -> Result<String> {
-    let mut grid: Grid = input.parse()?;
+fn part1(grid: &Grid) -> Result<u32> {
+    let mut grid = grid.clone();
     let mut round_count = 0;
     while grid.update_all() {
         round_count += 1;
     }
 
     let total_health: u32 = grid.units.iter().map(|unit| unit.health as u32).sum();
-
-    Ok(format!(
-        "{} * {} = {}",
-        round_count,
-        total_health,
-        round_count * total_health
-    ))
+    Ok(round_count * total_health)
 }
 
-fn part2(input: &str) -> Result<String> {
-    let grid: Grid = input.parse()?;
-
+// The lowest attack power with which the elves win without losses, with the
+// number of rounds and the total health left after that battle
+fn minimum_attack_power(grid: &Grid) -> Result<(u8, u32, u32)> {
     // Inclusive bounds for a binary search over attack power
     let mut min_ap = 3;
     let mut max_ap = 100;
@@ -523,13 +519,12 @@ fn part2(input: &str) -> Result<String> {
     };
 
     let total_health: u32 = grid.units.iter().map(|unit| unit.health as u32).sum();
-    Ok(format!(
-        "{} * {} = {} ({} attack power)",
-        round_count,
-        total_health,
-        round_count * total_health,
-        attack_power,
-    ))
+    Ok((attack_power, round_count, total_health))
+}
+
+fn part2(grid: &Grid) -> Result<u32> {
+    let (_, round_count, total_health) = minimum_attack_power(grid)?;
+    Ok(round_count * total_health)
 }
 
 #[test]
@@ -745,35 +740,35 @@ fn day15_test() {
 #.#.#G#
 #..G#E#
 #.....#
-#######" => "47 * 590 = 27730",
+#######" => 27730,
 "#######
 #G..#E#
 #E#E.E#
 #G.##.#
 #...#E#
 #...E.#
-#######" => "37 * 982 = 36334",
+#######" => 36334,
 "#######
 #E..EG#
 #.#G.E#
 #E.##E#
 #G..#.#
 #..E#.#
-#######" => "46 * 859 = 39514",
+#######" => 39514,
 "#######
 #E.G#.#
 #.#G..#
 #G.#.G#
 #G..#.#
 #...E.#
-#######" => "35 * 793 = 27755",
+#######" => 27755,
 "#######
 #.E...#
 #.#..G#
 #.###.#
 #E#G#G#
 #...#G#
-#######" => "54 * 536 = 28944",
+#######" => 28944,
 "#########
 #G......#
 #.E.#...#
@@ -782,38 +777,39 @@ fn day15_test() {
 #...#...#
 #.G...G.#
 #.....G.#
-#########" => "20 * 937 = 18740");
+#########" => 18740);
 
-    assert_results!(part2,
-"#######
+    // The attack power, rounds and remaining health of each battle
+    let examples = [
+        ("#######
 #.G...#
 #...EG#
 #.#.#G#
 #..G#E#
 #.....#
-#######" => "29 * 172 = 4988 (15 attack power)",
-"#######
+#######", (15, 29, 172)),
+        ("#######
 #E..EG#
 #.#G.E#
 #E.##E#
 #G..#.#
 #..E#.#
-#######" => "33 * 948 = 31284 (4 attack power)",
-"#######
+#######", (4, 33, 948)),
+        ("#######
 #E.G#.#
 #.#G..#
 #G.#.G#
 #G..#.#
 #...E.#
-#######" => "37 * 94 = 3478 (15 attack power)",
-"#######
+#######", (15, 37, 94)),
+        ("#######
 #.E...#
 #.#..G#
 #.###.#
 #E#G#G#
 #...#G#
-#######" => "39 * 166 = 6474 (12 attack power)",
-"#########
+#######", (12, 39, 166)),
+        ("#########
 #G......#
 #.E.#...#
 #..##..G#
@@ -821,6 +817,11 @@ fn day15_test() {
 #...#...#
 #.G...G.#
 #.....G.#
-#########" => "30 * 38 = 1140 (34 attack power)"
-);
+#########", (34, 30, 38)),
+    ];
+    for &(grid, expected) in &examples {
+        let grid: Grid = grid.parse().unwrap();
+        assert_eq!(minimum_attack_power(&grid).unwrap(), expected);
+        assert_eq!(part2(&grid).unwrap(), expected.1 * expected.2);
+    }
 }
@@ -1,208 +1,115 @@
 day!(
-    day16,
-    "https://adventofcode.com/2018/day/16/input",
+    2018,
+    16,
+    parse_input => (Vec<Sample>, Vec<RawInstruction>),
     part1,
     part2
 );
 
-use regex::Regex;
+use crate::elfcode::{Machine, Opcode, Program, RawInstruction, Value};
+use crate::error::{Location, ResultExt};
+use regex::{Captures, Regex};
 use smallvec::SmallVec;
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy)]
-struct Register(u8);
-#[derive(Debug, Clone, Copy)]
-struct Immediate(u8);
-type Registers = [i64; 4];
+type Registers = [Value; 4];
 
 #[derive(Debug, Clone)]
-struct Sample {
-    before: Registers,
-    placeholder: PlaceholderInstruction,
-    after: Registers,
+pub struct Sample {
+    pub before: Registers,
+    pub instruction: RawInstruction,
+    pub after: Registers,
 }
 
-#[derive(Debug, Clone, Copy)]
-struct PlaceholderInstruction(u8, u8, u8, u8);
-#[derive(Debug, Clone, Copy)]
-struct Instruction {
-    opcode: Opcode,
-    target: Register,
+fn parse_sample(captures: &Captures) -> Result<Sample> {
+    let before: Registers = [
+        captures[1].parse()?,
+        captures[2].parse()?,
+        captures[3].parse()?,
+        captures[4].parse()?,
+    ];
+    let instruction = RawInstruction {
+        number: captures[5].parse()?,
+        a: captures[6].parse()?,
+        b: captures[7].parse()?,
+        c: captures[8].parse()?,
+    };
+    let after: Registers = [
+        captures[9].parse()?,
+        captures[10].parse()?,
+        captures[11].parse()?,
+        captures[12].parse()?,
+    ];
+    Ok(Sample {
+        before,
+        instruction,
+        after,
+    })
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Opcode {
-    AddR(Register, Register),
-    AddI(Register, Immediate),
-    MulR(Register, Register),
-    MulI(Register, Immediate),
-    BanR(Register, Register),
-    BanI(Register, Immediate),
-    BorR(Register, Register),
-    BorI(Register, Immediate),
-    SetR(Register),
-    SetI(Immediate),
-    GtIR(Immediate, Register),
-    GtRI(Register, Immediate),
-    GtRR(Register, Register),
-    EqIR(Immediate, Register),
-    EqRI(Register, Immediate),
-    EqRR(Register, Register),
+fn parse_instruction(captures: &Captures) -> Result<RawInstruction> {
+    Ok(RawInstruction {
+        number: captures[1].parse()?,
+        a: captures[2].parse()?,
+        b: captures[3].parse()?,
+        c: captures[4].parse()?,
+    })
 }
 
-impl Register {
-    fn resolve(&self, registers: &Registers) -> i64 {
-        registers[self.0 as usize]
-    }
-}
-impl Immediate {
-    fn resolve(&self, _registers: &Registers) -> i64 {
-        self.0 as i64
-    }
-}
-
-impl From<PlaceholderInstruction> for Instruction {
-    fn from(placeholder: PlaceholderInstruction) -> Instruction {
-        let opcode = #[rustfmt::skip] match placeholder.0 {
-            0  => Opcode::AddR( Register(placeholder.1),  Register(placeholder.2)),
-            1  => Opcode::AddI( Register(placeholder.1), Immediate(placeholder.2)),
-            2  => Opcode::MulR( Register(placeholder.1),  Register(placeholder.2)),
-            3  => Opcode::MulI( Register(placeholder.1), Immediate(placeholder.2)),
-            4  => Opcode::BanR( Register(placeholder.1),  Register(placeholder.2)),
-            5  => Opcode::BanI( Register(placeholder.1), Immediate(placeholder.2)),
-            6  => Opcode::BorR( Register(placeholder.1),  Register(placeholder.2)),
-            7  => Opcode::BorI( Register(placeholder.1), Immediate(placeholder.2)),
-            8  => Opcode::SetR( Register(placeholder.1)),
-            9  => Opcode::SetI(Immediate(placeholder.1)),
-            10 => Opcode::GtIR(Immediate(placeholder.1),  Register(placeholder.2)),
-            11 => Opcode::GtRI( Register(placeholder.1), Immediate(placeholder.2)),
-            12 => Opcode::GtRR( Register(placeholder.1),  Register(placeholder.2)),
-            13 => Opcode::EqIR(Immediate(placeholder.1),  Register(placeholder.2)),
-            14 => Opcode::EqRI( Register(placeholder.1), Immediate(placeholder.2)),
-            15 => Opcode::EqRR( Register(placeholder.1),  Register(placeholder.2)),
-            _ => panic!("opcode out of range"),
-        };
-        Instruction {
-            opcode,
-            target: Register(placeholder.3),
-        }
-    }
-}
-
-impl Instruction {
-    fn execute(&self, reg: &Registers) -> Registers {
-        let mut result = reg.clone();
-        let new_value = #[rustfmt::skip] match self.opcode {
-            Opcode::AddR(a, b) => a.resolve(reg) + b.resolve(reg),
-            Opcode::AddI(a, b) => a.resolve(reg) + b.resolve(reg),
-            Opcode::MulR(a, b) => a.resolve(reg) * b.resolve(reg),
-            Opcode::MulI(a, b) => a.resolve(reg) * b.resolve(reg),
-            Opcode::BanR(a, b) => a.resolve(reg) & b.resolve(reg),
-            Opcode::BanI(a, b) => a.resolve(reg) & b.resolve(reg),
-            Opcode::BorR(a, b) => a.resolve(reg) | b.resolve(reg),
-            Opcode::BorI(a, b) => a.resolve(reg) | b.resolve(reg),
-            Opcode::SetR(a   ) => a.resolve(reg),
-            Opcode::SetI(a   ) => a.resolve(reg),
-            Opcode::GtIR(a, b) => if a.resolve(reg)  > b.resolve(reg) { 1 } else { 0 },
-            Opcode::GtRI(a, b) => if a.resolve(reg)  > b.resolve(reg) { 1 } else { 0 },
-            Opcode::GtRR(a, b) => if a.resolve(reg)  > b.resolve(reg) { 1 } else { 0 },
-            Opcode::EqIR(a, b) => if a.resolve(reg) == b.resolve(reg) { 1 } else { 0 },
-            Opcode::EqRI(a, b) => if a.resolve(reg) == b.resolve(reg) { 1 } else { 0 },
-            Opcode::EqRR(a, b) => if a.resolve(reg) == b.resolve(reg) { 1 } else { 0 },
-        };
-        result[self.target.0 as usize] = new_value;
-        result
-    }
-}
-
-fn parse_input(input: &str) -> Result<(Vec<Sample>, Vec<PlaceholderInstruction>)> {
+pub fn parse_input(input: &str) -> Result<(Vec<Sample>, Vec<RawInstruction>)> {
     let split_point = input.find("\n\n\n").ok_or(Error::Input("no split point"))?;
 
     lazy_static! {
         static ref RE1: Regex = Regex::new(r"Before: \[(\d+), (\d+), (\d+), (\d+)\]\n(\d+) (\d+) (\d+) (\d+)\nAfter:  \[(\d+), (\d+), (\d+), (\d+)\]").unwrap();
         static ref RE2: Regex = Regex::new(r"(?m)^(\d+) (\d+) (\d+) (\d+)$").unwrap();
     }
+    let at = |offset: usize| Location::at_offset(input, offset);
 
-    let samples = &input[0..split_point];
     let samples = RE1
-        .captures_iter(samples)
-        .map(|captures| {
-            let before: Registers = [
-                captures[1].parse()?,
-                captures[2].parse()?,
-                captures[3].parse()?,
-                captures[4].parse()?,
-            ];
-            let placeholder = PlaceholderInstruction(
-                captures[5].parse()?,
-                captures[6].parse()?,
-                captures[7].parse()?,
-                captures[8].parse()?,
-            );
-            let after: Registers = [
-                captures[9].parse()?,
-                captures[10].parse()?,
-                captures[11].parse()?,
-                captures[12].parse()?,
-            ];
-            Ok(Sample {
-                before,
-                placeholder,
-                after,
-            })
-        })
+        .captures_iter(&input[0..split_point])
+        .map(|captures| parse_sample(&captures).at(at(captures.get(0).unwrap().start())))
         .collect::<Result<Vec<_>>>()?;
 
-    let instructions = &input[split_point + 3..];
+    let start = split_point + 3;
     let instructions = RE2
-        .captures_iter(instructions)
+        .captures_iter(&input[start..])
         .map(|captures| {
-            Ok(PlaceholderInstruction(
-                captures[1].parse()?,
-                captures[2].parse()?,
-                captures[3].parse()?,
-                captures[4].parse()?,
-            ))
+            parse_instruction(&captures).at(at(start + captures.get(0).unwrap().start()))
         })
         .collect::<Result<Vec<_>>>()?;
 
     Ok((samples, instructions))
 }
 
-fn find_possible_opcodes(sample: &Sample) -> SmallVec<[u8; 16]> {
+fn find_possible_opcodes(sample: &Sample) -> SmallVec<[Opcode; 16]> {
     let mut res = SmallVec::new();
 
-    for i in 0..16 {
-        let mut current_instruction = sample.placeholder;
-        current_instruction.0 = i;
-        let old_registers = sample.before;
-        let instruction: Instruction = current_instruction.into();
-        let new_registers = instruction.execute(&old_registers);
-        if new_registers == sample.after {
-            res.push(i);
+    for &opcode in Opcode::ALL.iter() {
+        let mut registers = sample.before;
+        let instruction = sample.instruction.decode(opcode);
+        if instruction.execute(&mut registers).is_ok() && registers == sample.after {
+            res.push(opcode);
         }
     }
 
     res
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let (samples, _) = parse_input(input)?;
+fn part1((samples, _): &(Vec<Sample>, Vec<RawInstruction>)) -> Result<usize> {
     Ok(samples
-        .into_iter()
+        .iter()
         .filter(|sample| find_possible_opcodes(sample).len() >= 3)
         .count())
 }
 
-fn part2(input: &str) -> Result<i64> {
-    let (samples, instructions) = parse_input(input)?;
-    let mut mapping: HashMap<u8, u8> = HashMap::new();
+fn part2((samples, instructions): &(Vec<Sample>, Vec<RawInstruction>)) -> Result<Value> {
+    let mut mapping: HashMap<Value, Opcode> = HashMap::new();
     let mut new_mapping = Vec::new();
     while mapping.len() < 16 {
         new_mapping.extend(
             samples
                 .iter()
-                .filter(|sample| !mapping.contains_key(&sample.placeholder.0))
+                .filter(|sample| !mapping.contains_key(&sample.instruction.number))
                 .filter_map(|sample| {
                     let mut possible_opcodes = find_possible_opcodes(sample);
                     for i in (0..possible_opcodes.len()).rev() {
@@ -212,7 +119,7 @@ fn part2(input: &str) -> Result<i64> {
                         }
                     }
                     if possible_opcodes.len() == 1 {
-                        Some((sample.placeholder.0, possible_opcodes[0]))
+                        Some((sample.instruction.number, possible_opcodes[0]))
                     } else {
                         None
                     }
@@ -221,14 +128,15 @@ fn part2(input: &str) -> Result<i64> {
         mapping.extend(new_mapping.drain(..));
     }
 
-    let mut registers = [0, 0, 0, 0];
-    for mut placeholder in instructions {
-        placeholder.0 = mapping[&placeholder.0];
-        let instruction: Instruction = placeholder.into();
-        registers = instruction.execute(&registers);
-    }
+    let opcodes = (0..16)
+        .map(|number| mapping.get(&number).cloned())
+        .collect::<Option<Vec<_>>>()
+        .ok_or(Error::Input("opcode numbers are not 0 to 15"))?;
+    let program = Program::decode(instructions, &opcodes)?;
+    let mut machine = Machine::<Registers>::new(&program);
+    machine.run()?;
 
-    Ok(registers[0])
+    Ok(machine.registers[0])
 }
 
 #[test]
@@ -238,9 +146,14 @@ fn day16_test() {
     // After:  [3, 2, 2, 1]
 
     assert_eq!(
-        smallvec![1u8, 2u8, 9u8] as SmallVec<[u8; 16]>,
+        smallvec![Opcode::Addi, Opcode::Mulr, Opcode::Seti] as SmallVec<[Opcode; 16]>,
         find_possible_opcodes(&Sample {
-            placeholder: PlaceholderInstruction(9, 2, 1, 2),
+            instruction: RawInstruction {
+                number: 9,
+                a: 2,
+                b: 1,
+                c: 2,
+            },
             before: [3, 2, 1, 1],
             after: [3, 2, 2, 1],
         })
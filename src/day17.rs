@@ -1,10 +1,13 @@
 day!(
-    day17,
-    "https://adventofcode.com/2018/day/17/input",
+    2018,
+    17,
+    Layout::from_str => Layout,
     part1,
-    part2
+    part2,
+    examples: [part1(0 => 0), part2(0 => 1)]
 );
 
+use crate::error::parse_lines;
 use regex::Regex;
 use std::fmt::{self, Display, Formatter};
 use std::mem::drop;
@@ -33,7 +36,7 @@ impl Cell {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Layout {
+pub struct Layout {
     cells: Vec<Vec<Cell>>,
     height_range: RangeInclusive<usize>,
 }
@@ -42,19 +45,21 @@ impl FromStr for Layout {
     type Err = Error;
     fn from_str(s: &str) -> Result<Layout> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(r"(?m)^(x|y)=(\d+), (x|y)=(\d+)..(\d+)$").unwrap();
+            static ref RE: Regex = Regex::new(r"^(x|y)=(\d+), (x|y)=(\d+)..(\d+)$").unwrap();
         }
+        // Whether the single coordinate is x, and the range of the other one
+        let parse_vein = |line: &str| -> Result<(bool, usize, usize, usize)> {
+            let c = RE.captures(line).ok_or(Error::Input("expected a vein of clay"))?;
+            Ok((&c[1] == "x", c[2].parse()?, c[4].parse()?, c[5].parse()?))
+        };
+
         let mut layout = Layout {
             cells: Vec::new(),
             height_range: 0..=0,
         };
         let mut ymin: Option<usize> = None;
         let mut ymax: Option<usize> = None;
-        for c in RE.captures_iter(s) {
-            let is_a_x = &c[1] == "x";
-            let a = c[2].parse()?;
-            let lower = c[4].parse()?;
-            let upper = c[5].parse()?;
+        for (is_a_x, a, lower, upper) in parse_lines(s, parse_vein)? {
             for b in lower..=upper {
                 let (x, y) = if is_a_x { (a, b) } else { (b, a) };
                 ymin = Some(ymin.unwrap_or(y).min(y));
@@ -247,8 +252,8 @@ impl Layout {
     }
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let mut layout = Layout::from_str(input)?;
+fn part1(layout: &Layout) -> Result<usize> {
+    let mut layout = layout.clone();
     layout.flood_from(Vec2::new(500, 0))?;
     Ok(layout
         .cells
@@ -261,8 +266,8 @@ fn part1(input: &str) -> Result<usize> {
         .count())
 }
 
-fn part2(input: &str) -> Result<usize> {
-    let mut layout = Layout::from_str(input)?;
+fn part2(layout: &Layout) -> Result<usize> {
+    let mut layout = layout.clone();
     layout.flood_from(Vec2::new(500, 0))?;
     Ok(layout
         .cells
@@ -1,10 +1,12 @@
 day!(
-    day18,
-    "https://adventofcode.com/2018/day/18/input",
+    2018,
+    18,
+    CollectionArea::from_str => CollectionArea,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
 use smallvec::SmallVec;
 use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
@@ -21,7 +23,7 @@ enum Cell {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct CollectionArea {
+pub struct CollectionArea {
     cells: Mat2,
 }
 
@@ -35,10 +37,10 @@ impl FromStr for CollectionArea {
         for (line_idx, line) in lines.enumerate() {
             line_count += 1;
             if line_idx >= size {
-                return Err(Error::Input("too many lines"));
+                return Err(Error::Input("too many lines")).at_line(line_idx + 1, line);
             }
             if size != line.len() {
-                return Err(Error::Input("inconsistent width"));
+                return Err(Error::Input("inconsistent width")).at_line(line_idx + 1, line);
             }
 
             for (x, c) in line.chars().enumerate() {
@@ -46,7 +48,10 @@ impl FromStr for CollectionArea {
                     '.' => Cell::Open,
                     '|' => Cell::Trees,
                     '#' => Cell::Lumberyard,
-                    _ => return Err(Error::Input("invalid character")),
+                    _ => {
+                        return Err(Error::Input("invalid character"))
+                            .at(Location::cell(line_idx + 1, x + 1, line));
+                    }
                 };
             }
         }
@@ -179,14 +184,14 @@ impl CollectionArea {
         (trees_count, lumberyards_count)
     }
 
-    fn resource_value_str(&self) -> String {
+    fn total_resource_value(&self) -> usize {
         let (trees_count, lumberyards_count) = self.resource_value();
-        format!("{} * {} = {}", trees_count, lumberyards_count, trees_count * lumberyards_count)
+        trees_count * lumberyards_count
     }
 }
 
-fn part1(input: &str) -> Result<String> {
-    let mut fore_area = CollectionArea::from_str(input)?;
+fn part1(area: &CollectionArea) -> Result<usize> {
+    let mut fore_area = area.clone();
     let mut back_area = fore_area.clone();
     
     for _ in 0..10 {
@@ -194,11 +199,11 @@ fn part1(input: &str) -> Result<String> {
         std::mem::swap(&mut fore_area, &mut back_area);
     }
 
-    Ok(fore_area.resource_value_str())
+    Ok(fore_area.total_resource_value())
 }
 
-fn part2(input: &str) -> Result<String> {
-    let mut fore_area = CollectionArea::from_str(input)?;
+fn part2(area: &CollectionArea) -> Result<usize> {
+    let mut fore_area = area.clone();
     let mut back_area = fore_area.clone();
 
     let mut previous_states = HashMap::new();
@@ -213,7 +218,7 @@ fn part2(input: &str) -> Result<String> {
             let cycle_length = update_index - previous_index;
             let offset = (ITERATIONS - previous_index) % cycle_length;
             if offset == 0 {
-                return Ok(fore_area.resource_value_str());
+                return Ok(fore_area.total_resource_value());
             }
             let goal_index = previous_index + offset;
             let result_state = previous_states.into_iter().filter_map(|(key, value)| {
@@ -225,11 +230,11 @@ fn part2(input: &str) -> Result<String> {
                 }
             }).next().unwrap();
             
-            return Ok(result_state.resource_value_str())
+            return Ok(result_state.total_resource_value())
         }
     }
 
-    Ok(fore_area.resource_value_str())
+    Ok(fore_area.total_resource_value())
 }
 
 #[test]
@@ -250,5 +255,5 @@ fn day18_test() {
         EXAMPLE
     );
 
-    assert_results!(part1, EXAMPLE => "37 * 31 = 1147");
+    assert_results!(part1, EXAMPLE => 1147);
 }
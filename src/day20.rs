@@ -1,10 +1,12 @@
 day!(
-    day20,
-    "https://adventofcode.com/2018/day/20/input",
+    2018,
+    20,
+    Layout::from_str => Layout,
     part1,
     part2
 );
 
+use crate::error::{Location, ResultExt};
 use crate::vec2::AabbIteratorEx;
 use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
 use std::fmt::{self, Display, Formatter};
@@ -24,7 +26,7 @@ bitflags! {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Layout {
+pub struct Layout {
     rooms: HashMap<Vec2, Room>,
 }
 
@@ -38,14 +40,15 @@ impl FromStr for Layout {
             return Err(Error::Input("expected some input"));
         }
 
-        let mut chars = s.chars().skip(1).take(s.len() - 2);
+        let mut chars = s.char_indices().skip(1).take(s.len() - 2);
         fn apply(
-            chars: &mut impl Iterator<Item = char>,
+            input: &str,
+            chars: &mut impl Iterator<Item = (usize, char)>,
             rooms: &mut HashMap<Vec2, Room>,
             start_pos: Vec2,
         ) -> Result<()> {
             let mut pos = start_pos;
-            while let Some(c) = chars.next() {
+            while let Some((index, c)) = chars.next() {
                 match c {
                     '|' => {
                         pos = start_pos;
@@ -53,7 +56,7 @@ impl FromStr for Layout {
                     }
                     ')' => return Ok(()),
                     '(' => {
-                        apply(chars, rooms, pos)?;
+                        apply(input, chars, rooms, pos)?;
                         continue;
                     }
                     _ => {}
@@ -65,7 +68,10 @@ impl FromStr for Layout {
                     'E' => { new_room = Room::W; *current_room |= Room::E; Vec2::new( 1, 0) },
                     'N' => { new_room = Room::S; *current_room |= Room::N; Vec2::new(0, -1) },
                     'S' => { new_room = Room::N; *current_room |= Room::S; Vec2::new(0,  1) },
-                    _ => return Err(Error::Input("invalid input character")),
+                    _ => {
+                        return Err(Error::Input("invalid input character"))
+                            .at(Location::at_offset(input, index));
+                    }
                 };
                 std::mem::drop(current_room);
                 pos += offset;
@@ -73,7 +79,7 @@ impl FromStr for Layout {
             }
             Ok(())
         }
-        apply(&mut chars, &mut rooms, Vec2::new(0, 0))?;
+        apply(s, &mut chars, &mut rooms, Vec2::new(0, 0))?;
 
         Ok(Layout { rooms })
     }
@@ -146,15 +152,13 @@ fn calculate_costs(layout: &Layout) -> HashMap<Vec2, u32> {
     costs
 }
 
-fn part1(input: &str) -> Result<u32> {
-    let layout = Layout::from_str(input)?;
-    let costs = calculate_costs(&layout);
+fn part1(layout: &Layout) -> Result<u32> {
+    let costs = calculate_costs(layout);
     Ok(costs.values().cloned().max().unwrap())
 }
 
-fn part2(input: &str) -> Result<usize> {
-    let layout = Layout::from_str(input)?;
-    let costs = calculate_costs(&layout);
+fn part2(layout: &Layout) -> Result<usize> {
+    let costs = calculate_costs(layout);
     Ok(costs.values().filter(|&&x| x >= 1000).count())
 }
 
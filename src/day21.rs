@@ -1,133 +1,102 @@
 day!(
-    day21,
-    "https://adventofcode.com/2018/day/21/input",
+    2018,
+    21,
+    parse_input => Analysis,
     part1,
     part2
 );
 
-use crate::day19::{Instruction, Opcode, Program, ProgramState, Register};
+use crate::compiler::Compiled;
+use crate::elfcode::{Opcode, Operand, Program, Value};
 use std::collections::HashSet;
 
-fn parse_input(input: &str) -> Result<(Program, u64)> {
+type Registers = [Value; 6];
+
+// The program only reads register 0 to compare it with another register, and
+// halts once they are equal
+pub struct Analysis {
+    compiled: Compiled,
+    comparison: usize, // the address of the comparison
+    register: usize,   // compared with register 0
+}
+
+pub fn parse_input(input: &str) -> Result<Analysis> {
     let program: Program = input.parse()?;
-    
-    // Validate the opcode keys
-    if program.instructions.len() != 31 {
-        return Err(Error::Input("expected 31 instructions"));
+    let compiled = Compiled::new(&program)?;
+    if program.ip_register == Some(0) {
+        return Err(Error::Input("expected register 0 not to be the instruction pointer"));
     }
 
-    macro_rules! validate_opcode {
-        ($program:expr, $idx:expr, $expected:expr) => {
-            if program.instructions[$idx].opcode.key() != $expected {
-                return Err(Error::Input("invalid opcode structure in input"))
-            }
+    let mut comparison = None;
+    for (address, instruction) in program.instructions.iter().enumerate() {
+        if instruction.c == 0 {
+            return Err(Error::Input("expected register 0 to never be written"));
+        }
+        let (a, b) = instruction.opcode.operands();
+        if !(a == Operand::Register && instruction.a == 0)
+            && !(b == Operand::Register && instruction.b == 0)
+        {
+            continue;
+        }
+        let register = match instruction.opcode {
+            Opcode::Eqrr => instruction.a.max(instruction.b),
+            _ => return Err(Error::Input("expected register 0 to only be compared")),
         };
+        if comparison.is_some() || register == 0 {
+            return Err(Error::Input("expected a single comparison with register 0"));
+        }
+        comparison = Some((address, register as usize));
     }
-    validate_opcode!(program, 00, "seti");
-    validate_opcode!(program, 01, "bani");
-    validate_opcode!(program, 02, "eqri");
-    validate_opcode!(program, 03, "addr");
-    validate_opcode!(program, 04, "seti");
-    validate_opcode!(program, 05, "seti");
-    validate_opcode!(program, 06, "bori");
-    validate_opcode!(program, 07, "seti");
-    validate_opcode!(program, 08, "bani");
-    validate_opcode!(program, 09, "addr");
-    validate_opcode!(program, 10, "bani");
-    validate_opcode!(program, 11, "muli");
-    validate_opcode!(program, 12, "bani");
-    validate_opcode!(program, 13, "gtir");
-    validate_opcode!(program, 14, "addr");
-    validate_opcode!(program, 15, "addi");
-    validate_opcode!(program, 16, "seti");
-    validate_opcode!(program, 17, "seti");
-    validate_opcode!(program, 18, "addi");
-    validate_opcode!(program, 19, "muli");
-    validate_opcode!(program, 20, "gtrr");
-    validate_opcode!(program, 21, "addr");
-    validate_opcode!(program, 22, "addi");
-    validate_opcode!(program, 23, "seti");
-    validate_opcode!(program, 24, "addi");
-    validate_opcode!(program, 25, "seti");
-    validate_opcode!(program, 26, "setr");
-    validate_opcode!(program, 27, "seti");
-    validate_opcode!(program, 28, "eqrr");
-    validate_opcode!(program, 29, "addr");
-    validate_opcode!(program, 30, "seti");
-
-    let mut register_map = [0; 6];
-    register_map[program.ip_register as usize] = 1;
-    register_map[program.instructions[0].target.0 as usize] = 2;
-    register_map[program.instructions[6].target.0 as usize] = 3;
-    register_map[program.instructions[8].target.0 as usize] = 4;
-    register_map[program.instructions[18].target.0 as usize] = 5;
 
-    let new_program = Program {
-        ip_register: 1,
-        instructions: program.instructions.iter().map(|instruction| {
-            let map_register = |r: Register| -> Register { Register(register_map[r.0 as usize]) };
-            Instruction {
-                target: map_register(instruction.target),
-                opcode: instruction.opcode.map_registers(map_register),
-            }
-        }).collect()
-    };
-    let initial_state = match new_program.instructions[7].opcode {
-        Opcode::SetI(a) => a.0,
-        _ => unreachable!(),
-    };
-
-    Ok((new_program, initial_state))
+    let (comparison, register) =
+        comparison.ok_or(Error::Input("expected a comparison with register 0"))?;
+    Ok(Analysis {
+        compiled,
+        comparison,
+        register,
+    })
 }
 
-fn puzzle(max_iter: u64, find_first: bool, initial_state: u64) -> Option<u64> {
-    let mut last = None;
-    let mut previous_values = HashSet::new();
-    let mut a: u64 = 0;
-    for _ in 0..max_iter {
-        let mut b: u64 = a | 65536;
-        a = initial_state;
-        loop {
-            a = a + (b & 255);
-            a = a & 16777215;
-            a = a * 65899;
-            a = a & 16777215;
-            if 256 > b { break; }
-            let mut c: u64 = 0;
-            loop {
-                let mut d: u64 = c + 1;
-                d = d * 256;
-                d = if d > b { 1 } else { 0 };
-                if d == 1 { break; }
-                c = c + 1;
+impl Analysis {
+    // Calls `f` with every value register 0 is compared with, until it returns
+    // false, the program halts or the registers repeat, after which no other
+    // values can be compared
+    fn compared<F: FnMut(Value) -> bool>(&self, mut f: F) -> Result<()> {
+        let mut registers = Registers::default();
+        let mut seen = HashSet::new();
+        let mut ip = 0;
+        while let Some(stop) = self.compiled.resume(&mut registers, ip, Some(self.comparison))? {
+            if !seen.insert(registers) || !f(registers[self.register]) {
+                break;
             }
-            b = c;
-        }
-        if find_first {
-            return Some(a);
-        }
-        if previous_values.insert(a) {
-            last = Some(a);
-        }
-        else {
-            return last;
+            ip = stop;
         }
+        Ok(())
     }
-    last
 }
 
-fn part1(input: &str) -> Result<u64> {
-    let (_, initial_state) = parse_input(input)?;
-    puzzle(1_000_000, true, initial_state).ok_or(Error::Input(
-        "cannot reach instruction in 1_000_000 iterations",
-    ))
+// Halts after the fewest instructions on the first value compared
+fn part1(analysis: &Analysis) -> Result<Value> {
+    let mut first = None;
+    analysis.compared(|value| {
+        first = Some(value);
+        false
+    })?;
+    first.ok_or(Error::Input("register 0 is never compared"))
 }
 
-fn part2(input: &str) -> Result<u64> {
-    let (_, initial_state) = parse_input(input)?;
-    puzzle(1_000_000_000_000, false, initial_state).ok_or(Error::Input(
-        "cannot reach instruction in 1_000_000 iterations",
-    ))
+// Halts after the most instructions on the last new value before the values
+// repeat
+fn part2(analysis: &Analysis) -> Result<Value> {
+    let mut values = HashSet::new();
+    let mut last = None;
+    analysis.compared(|value| {
+        if values.insert(value) {
+            last = Some(value);
+        }
+        true
+    })?;
+    last.ok_or(Error::Input("register 0 is never compared"))
 }
 
 #[test]
@@ -1,10 +1,12 @@
 day!(
-    day22,
-    "https://adventofcode.com/2018/day/22/input",
+    2018,
+    22,
+    Input::from_str => Input,
     part1,
     part2
 );
 
+use crate::error::ResultExt;
 use crate::mat2::Mat2;
 use num_traits::{One, Zero};
 use pathfinding::directed::astar::astar;
@@ -16,9 +18,9 @@ use std::str::FromStr;
 type Vec2 = crate::vec2::Vec2us;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Input {
-    depth: u32,
-    target: Vec2,
+pub struct Input {
+    pub depth: u32,
+    pub target: Vec2,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -76,14 +78,25 @@ impl FromStr for Input {
     type Err = Error;
     fn from_str(s: &str) -> Result<Input> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(r"^depth: (\d+)\ntarget: (\d+),(\d+)$").unwrap();
+            static ref DEPTH: Regex = Regex::new(r"^depth: (\d+)$").unwrap();
+            static ref TARGET: Regex = Regex::new(r"^target: (\d+),(\d+)$").unwrap();
         }
-        let c = RE
-            .captures(s)
-            .ok_or(Error::Input("invalid input structure"))?;
+        let mut lines = s.lines();
+        let (depth, target) = match (lines.next(), lines.next(), lines.next()) {
+            (Some(depth), Some(target), None) => (depth, target),
+            _ => return Err(Error::Input("expected a depth and a target line")),
+        };
+        let parse_depth = || -> Result<u32> {
+            let c = DEPTH.captures(depth).ok_or(Error::Input("expected the depth"))?;
+            Ok(c[1].parse()?)
+        };
+        let parse_target = || -> Result<Vec2> {
+            let c = TARGET.captures(target).ok_or(Error::Input("expected the target"))?;
+            Ok(Vec2::new(c[1].parse()?, c[2].parse()?))
+        };
         Ok(Input {
-            depth: c[1].parse()?,
-            target: Vec2::new(c[2].parse()?, c[3].parse()?),
+            depth: parse_depth().at_line(1, depth)?,
+            target: parse_target().at_line(2, target)?,
         })
     }
 }
@@ -173,8 +186,8 @@ impl RegionType {
     }
 }
 
-fn part1(input: &str) -> Result<u32> {
-    let cave = CaveSystem::from_input(&input.parse()?);
+fn part1(input: &Input) -> Result<u32> {
+    let cave = CaveSystem::from_input(input);
     let size = cave.layout.size();
     let mut risk = 0;
     for x in 0..size.x {
@@ -186,12 +199,10 @@ fn part1(input: &str) -> Result<u32> {
     Ok(risk)
 }
 
-fn part2(input: &str) -> Result<u32> {
-    let input = Input::from_str(input)?;
-    // 8 times the size will guarantee that the shortest path is in range
+fn part2(input: &Input) -> Result<u32> {    // 8 times the size will guarantee that the shortest path is in range
     // because if it'd need more than 8 times the size, the shortest path
     // could also just switch to a new tool every cell.
-    let cave = CaveSystem::new((input.target + Vec2::one()) * 8, &input);
+    let cave = CaveSystem::new((input.target + Vec2::one()) * 8, input);
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     struct Node {
@@ -1,10 +1,12 @@
 day!(
-    day23,
-    "https://adventofcode.com/2018/day/23/input",
+    2018,
+    23,
+    parse_nanobots => Vec<Nanobot>,
     part1,
     part2
 );
 
+use crate::error::parse_lines;
 use crate::vec3::AabbIteratorEx;
 use num_traits::{One, Signed, Zero};
 use regex::Regex;
@@ -15,9 +17,9 @@ use std::str::FromStr;
 type Vec3 = crate::vec3::Vec3i;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Nanobot {
-    pos: Vec3,
-    radius: i32,
+pub struct Nanobot {
+    pub pos: Vec3,
+    pub radius: i32,
 }
 
 impl Nanobot {
@@ -52,11 +54,11 @@ impl FromStr for Nanobot {
     }
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let nanobots = input
-        .lines()
-        .map(Nanobot::from_str)
-        .collect::<Result<Vec<_>>>()?;
+pub fn parse_nanobots(input: &str) -> Result<Vec<Nanobot>> {
+    parse_lines(input, Nanobot::from_str)
+}
+
+fn part1(nanobots: &[Nanobot]) -> Result<usize> {
     let largest_radius = nanobots
         .iter()
         .max_by_key(|nanobot| nanobot.radius)
@@ -80,12 +82,7 @@ fn round_up_to_next_power_of_2(mut v: u32) -> u32 {
     v
 }
 
-fn part2(input: &str) -> Result<i32> {
-    let nanobots = &input
-        .lines()
-        .map(Nanobot::from_str)
-        .collect::<Result<Vec<_>>>()?;
-
+fn part2(nanobots: &[Nanobot]) -> Result<i32> {
     if nanobots.is_empty() {
         return Err(Error::Input("empty input"));
     }
@@ -131,7 +128,7 @@ fn part2(input: &str) -> Result<i32> {
         make_cube(nanobots, min_corner, initial_size).intersections as usize
     );
 
-    fn make_cube(nanobots: &Vec<Nanobot>, pos: Vec3, size: i32) -> Cube {
+    fn make_cube(nanobots: &[Nanobot], pos: Vec3, size: i32) -> Cube {
         let mut intersections = 0;
         let size_vec = Vec3::one() * (size - 1);
         for nanobot in nanobots {
@@ -1,16 +1,19 @@
 day!(
-    day24,
-    "https://adventofcode.com/2018/day/24/input",
+    2018,
+    24,
+    Armies::from_str => Armies,
     part1,
-    part2
+    part2,
+    examples: [part1(0 => 0)]
 );
 
+use crate::error::{Location, ResultExt};
 use regex::Regex;
 use std::collections::HashSet;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum DamageType {
+pub enum DamageType {
     Fire,
     Cold,
     Bludgeoning,
@@ -33,13 +36,13 @@ impl FromStr for DamageType {
 }
 
 #[derive(Debug, Clone)]
-struct Group {
-    units: u32,
-    hp: u32,
-    attack: (u32, DamageType),
-    initiative: u32,
-    weaknesses: HashSet<DamageType>,
-    immunities: HashSet<DamageType>,
+pub struct Group {
+    pub units: u32,
+    pub hp: u32,
+    pub attack: (u32, DamageType),
+    pub initiative: u32,
+    pub weaknesses: HashSet<DamageType>,
+    pub immunities: HashSet<DamageType>,
 }
 
 impl FromStr for Group {
@@ -107,9 +110,9 @@ impl FromStr for Group {
 }
 
 #[derive(Debug, Clone)]
-struct Armies {
-    immune_system: Vec<Group>,
-    infection: Vec<Group>,
+pub struct Armies {
+    pub immune_system: Vec<Group>,
+    pub infection: Vec<Group>,
 }
 
 impl FromStr for Armies {
@@ -117,18 +120,24 @@ impl FromStr for Armies {
     fn from_str(s: &str) -> Result<Armies> {
         lazy_static! {
             static ref RE: Regex = Regex::new(
-                r"^Immune System:
-((?s).+)
-
-Infection:
-((?s).+)$"
+                r"^Immune System:\r?\n((?s).+)\r?\n\r?\nInfection:\r?\n((?s).+)$"
             )
             .unwrap();
+            static ref LINE: Regex = Regex::new(r"[^\r\n]+").unwrap();
         }
         let c = RE.captures(s).ok_or(Error::Input("invalid input format"))?;
+        let parse_groups = |index| {
+            let groups = c.get(index).unwrap();
+            LINE.find_iter(groups.as_str())
+                .map(|line| {
+                    let offset = groups.start() + line.start();
+                    Group::from_str(line.as_str()).at(Location::at_offset(s, offset))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
         Ok(Armies {
-            immune_system: c[1].lines().map(Group::from_str).collect::<Result<_>>()?,
-            infection: c[2].lines().map(Group::from_str).collect::<Result<_>>()?,
+            immune_system: parse_groups(1)?,
+            infection: parse_groups(2)?,
         })
     }
 }
@@ -281,8 +290,8 @@ impl Armies {
     }
 }
 
-fn part1(input: &str) -> Result<u32> {
-    let mut armies: Armies = input.parse()?;
+fn part1(armies: &Armies) -> Result<u32> {
+    let mut armies = armies.clone();
 
     while armies.fight() > 0 {}
     Ok(armies
@@ -293,13 +302,12 @@ fn part1(input: &str) -> Result<u32> {
         .sum())
 }
 
-fn part2(input: &str) -> Result<String> {
-    let initial_armies: Armies = input.parse()?;
-
+// The smallest boost with which the immune system wins
+fn minimum_boost(initial_armies: &Armies) -> u32 {
     // Find an amount that'd let the immune system win
     let mut min_bonus = 0; // exclusive
     let mut max_bonus = 1; // inclusive
-    let bonus = loop {
+    loop {
         let mut armies = initial_armies.clone();
         armies.boost_immune_system(max_bonus);
         match armies.fight_to_victory() {
@@ -331,21 +339,19 @@ fn part2(input: &str) -> Result<String> {
                 }
             }
         };
-    };
+    }
+}
 
-    let mut armies = initial_armies;
-    armies.boost_immune_system(bonus);
+fn part2(initial_armies: &Armies) -> Result<u32> {
+    let mut armies = initial_armies.clone();
+    armies.boost_immune_system(minimum_boost(initial_armies));
     armies.fight_to_victory();
-    
-    Ok(format!(
-        "{} => {}",
-        bonus,
-        armies
-            .immune_system
-            .iter()
-            .map(|group| group.units)
-            .sum::<u32>()
-    ))
+
+    Ok(armies
+        .immune_system
+        .iter()
+        .map(|group| group.units)
+        .sum())
 }
 
 #[test]
@@ -360,5 +366,16 @@ Infection:
 4485 units each with 2961 hit points (immune to radiation; weak to fire, cold) with an attack that does 12 slashing damage at initiative 4";
 
     assert_results!(part1, EXAMPLE => 5216);
-    assert_results!(part2, EXAMPLE => "1570 => 51");
+    assert_results!(part2, EXAMPLE => 51);
+    assert_eq!(minimum_boost(&EXAMPLE.parse().unwrap()), 1570);
+
+    // Errors point at the group, whatever the line endings
+    let invalid = EXAMPLE.replace("989 units", "989 unit").replace('\n', "\r\n");
+    match invalid.parse::<Armies>() {
+        Err(Error::Located(location, _)) => {
+            assert_eq!((location.line, location.column), (3, Some(1)));
+            assert!(location.snippet.starts_with("989 unit each"));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
 }
@@ -1,43 +1,42 @@
 day!(
-    day25,
-    "https://adventofcode.com/2018/day/25/input",
+    2018,
+    25,
+    parse_input => Vec<Point>,
     part1,
     !
 );
 
+use crate::error::parse_lines;
 use std::cell::RefCell;
 use std::collections::hash_map::{Entry, HashMap};
 use std::iter::repeat;
 use std::mem::swap;
 
-type Point = [i32; 4];
+pub type Point = [i32; 4];
 
-fn parse_input(input: &str) -> Result<Vec<Point>> {
-    let points = input
-        .lines()
-        .map(|line| {
-            let mut i = line.split(',');
-            let p: Point = [
-                i.next()
-                    .ok_or(Error::Input("expected 4 numbers, got 0"))?
-                    .parse()?,
-                i.next()
-                    .ok_or(Error::Input("expected 4 numbers, got 1"))?
-                    .parse()?,
-                i.next()
-                    .ok_or(Error::Input("expected 4 numbers, got 2"))?
-                    .parse()?,
-                i.next()
-                    .ok_or(Error::Input("expected 4 numbers, got 3"))?
-                    .parse()?,
-            ];
-            if i.next().is_some() {
-                Err(Error::Input("expected end of line after 4 numbers"))
-            } else {
-                Ok(p)
-            }
-        })
-        .collect::<Result<Vec<_>>>()?;
+pub fn parse_input(input: &str) -> Result<Vec<Point>> {
+    let points = parse_lines(input, |line| {
+        let mut i = line.split(',');
+        let p: Point = [
+            i.next()
+                .ok_or(Error::Input("expected 4 numbers, got 0"))?
+                .parse()?,
+            i.next()
+                .ok_or(Error::Input("expected 4 numbers, got 1"))?
+                .parse()?,
+            i.next()
+                .ok_or(Error::Input("expected 4 numbers, got 2"))?
+                .parse()?,
+            i.next()
+                .ok_or(Error::Input("expected 4 numbers, got 3"))?
+                .parse()?,
+        ];
+        if i.next().is_some() {
+            Err(Error::Input("expected end of line after 4 numbers"))
+        } else {
+            Ok(p)
+        }
+    })?;
     if points.len() < 2 {
         return Err(Error::Input("expected at least 2 points"));
     }
@@ -57,8 +56,7 @@ struct Constellation {
     points: Vec<Point>,
 }
 
-fn part1(input: &str) -> Result<usize> {
-    let points = parse_input(input)?;
+fn part1(points: &[Point]) -> Result<usize> {
     let mut constellations: Vec<Constellation> = Vec::new();
     let mut map: Vec<Option<usize>> = repeat(None).take(points.len()).collect();
 
@@ -181,4 +179,7 @@ fn day25_test() {
 -2,2,3,-1
 1,2,2,0
 -1,-2,0,-2" => 8);
+
+    use crate::framework::Solver;
+    assert!(Solution::part2(&Solution::parse("0,0,0,0\n1,1,1,1").unwrap()).is_err());
 }
@@ -0,0 +1,507 @@
+// Interactive debugger for ElfCode programs, behind the debug command
+use crate::elfcode::{Machine, Program, Registers, Value};
+use crate::{Error, Result};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+const HELP: &str = "commands:
+    step [N]               execute N instructions (default 1)
+    continue               run until a breakpoint, a watch or the end
+    break ip N             stop before instruction N
+    break rX OP VALUE      stop once register X compares to VALUE (== != < <= > >=)
+    breakpoints            list the breakpoints
+    delete N               delete breakpoint N
+    watch rX               stop whenever register X changes
+    unwatch rX             stop watching register X
+    registers              show the instruction pointer and registers
+    set rX VALUE           change a register
+    set ip N               jump to instruction N
+    list                   disassemble the program, with how often each instruction ran
+    hits                   the instructions that ran most often
+    trace FILE|-|off       log every executed instruction to FILE, stdout or nowhere
+    reset                  restart the program, keeping breakpoints and watches
+    quit
+An empty line repeats the previous command.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    const ALL: [Comparison; 6] = [
+        Comparison::Eq,
+        Comparison::Ne,
+        Comparison::Lt,
+        Comparison::Le,
+        Comparison::Gt,
+        Comparison::Ge,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+
+    pub fn holds(self, a: Value, b: Value) -> bool {
+        match self {
+            Comparison::Eq => a == b,
+            Comparison::Ne => a != b,
+            Comparison::Lt => a < b,
+            Comparison::Le => a <= b,
+            Comparison::Gt => a > b,
+            Comparison::Ge => a >= b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    Ip(usize),                          // before the instruction runs
+    Register(usize, Comparison, Value), // once the condition starts to hold
+}
+
+impl Breakpoint {
+    fn is_hit(&self, ip: usize, registers: &[Value]) -> bool {
+        match *self {
+            Breakpoint::Ip(target) => ip == target,
+            Breakpoint::Register(register, comparison, value) => {
+                comparison.holds(registers[register], value)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::Ip(ip) => write!(f, "ip {}", ip),
+            Breakpoint::Register(register, comparison, value) => {
+                write!(f, "r{} {} {}", register, comparison.as_str(), value)
+            }
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Breakpoint> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            ["ip", ip] => Ok(Breakpoint::Ip(parse_number(ip)?)),
+            [register, comparison, value] => {
+                let comparison = Comparison::ALL
+                    .iter()
+                    .cloned()
+                    .find(|c| c.as_str() == *comparison)
+                    .ok_or_else(|| usage(format!("unknown comparison {}", comparison)))?;
+                Ok(Breakpoint::Register(
+                    parse_register(register)?,
+                    comparison,
+                    parse_number(value)?,
+                ))
+            }
+            _ => Err(usage("expected break ip N or break rX OP VALUE".to_owned())),
+        }
+    }
+}
+
+fn usage(message: String) -> Error {
+    Error::Usage(message)
+}
+
+fn parse_number<T: FromStr>(s: &str) -> Result<T> {
+    s.parse().map_err(|_| usage(format!("{} is not a number", s)))
+}
+
+fn parse_register(s: &str) -> Result<usize> {
+    if s.starts_with('r') {
+        if let Ok(register) = s[1..].parse() {
+            return Ok(register);
+        }
+    }
+    Err(usage(format!("{} is not a register, like r0", s)))
+}
+
+// Why the program stopped running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    Breakpoint(usize),          // the index of the breakpoint
+    Watch(usize, Value, Value), // the register, and its old and new value
+    Steps,                      // ran the requested number of instructions
+}
+
+pub struct Debugger<'a, R> {
+    pub machine: Machine<'a, R>,
+    pub breakpoints: Vec<Breakpoint>,
+    pub watches: Vec<usize>,
+    pub hits: Vec<u64>, // how often each instruction ran
+    pub steps: u64,
+    held: Vec<Breakpoint>, // the breakpoints that held when the program last stopped
+    trace: Option<Box<dyn Write>>,
+}
+
+impl<'a, R: Registers> Debugger<'a, R> {
+    pub fn new(program: &'a Program) -> Debugger<'a, R> {
+        Debugger::with_registers(program, R::default())
+    }
+
+    pub fn with_registers(program: &'a Program, registers: R) -> Debugger<'a, R> {
+        Debugger {
+            machine: Machine::with_registers(program, registers),
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+            hits: vec![0; program.instructions.len()],
+            steps: 0,
+            held: Vec::new(),
+            trace: None,
+        }
+    }
+
+    // Restarts the program with every register cleared, keeping their number
+    pub fn reset(&mut self) {
+        let mut registers = self.machine.registers.clone();
+        registers.as_mut().iter_mut().for_each(|value| *value = 0);
+        self.machine = Machine::with_registers(self.machine.program, registers);
+        self.hits = vec![0; self.machine.program.instructions.len()];
+        self.steps = 0;
+        self.held.clear();
+    }
+
+    // Logs every executed instruction like the puzzle description does
+    pub fn set_trace(&mut self, trace: Option<Box<dyn Write>>) {
+        self.trace = trace;
+    }
+
+    fn check_register(&self, register: usize) -> Result<usize> {
+        if register < self.machine.registers.as_ref().len() {
+            Ok(register)
+        } else {
+            Err(usage(format!("there is no register r{}", register)))
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> Result<()> {
+        if let Breakpoint::Register(register, _, _) = breakpoint {
+            self.check_register(register)?;
+        }
+        self.breakpoints.push(breakpoint);
+        Ok(())
+    }
+
+    pub fn watch(&mut self, register: usize) -> Result<()> {
+        let register = self.check_register(register)?;
+        if !self.watches.contains(&register) {
+            self.watches.push(register);
+        }
+        Ok(())
+    }
+
+    // Executes a single instruction, and reports a watched register that changed
+    pub fn step(&mut self) -> Result<Option<Stop>> {
+        let ip = self.machine.ip;
+        let mut before = self.machine.registers.clone();
+        if let Some(ip_register) = self.machine.program.ip_register {
+            if let Some(value) = before.as_mut().get_mut(ip_register) {
+                *value = ip as Value;
+            }
+        }
+        if !self.machine.step()? {
+            return Ok(Some(Stop::Halted));
+        }
+        self.steps += 1;
+        self.hits[ip] += 1;
+
+        let after = self.machine.registers.as_ref();
+        if let Some(ref mut trace) = self.trace {
+            writeln!(
+                trace,
+                "ip={} {:?} {} {:?}",
+                ip,
+                before.as_ref(),
+                self.machine.program.instructions[ip],
+                after
+            )?;
+        }
+        for &register in &self.watches {
+            let (old, new) = (before.as_ref()[register], after[register]);
+            if old != new {
+                return Ok(Some(Stop::Watch(register, old, new)));
+            }
+        }
+        Ok(None)
+    }
+
+    // Runs until something stops the program, or `max_steps` instructions ran.
+    // Breakpoints only stop the program when they start to hold, so it can
+    // always continue from one, but a breakpoint that started to hold while the
+    // program was stopped, like one on the current instruction, stops it again
+    // before the first step.
+    pub fn run(&mut self, max_steps: Option<u64>) -> Result<Stop> {
+        let stop = self.run_steps(max_steps);
+        self.held = self
+            .holding_breakpoints()
+            .into_iter()
+            .zip(&self.breakpoints)
+            .filter(|&(holds, _)| holds)
+            .map(|(_, &breakpoint)| breakpoint)
+            .collect();
+        stop
+    }
+
+    fn run_steps(&mut self, max_steps: Option<u64>) -> Result<Stop> {
+        let hit = self
+            .holding_breakpoints()
+            .into_iter()
+            .zip(&self.breakpoints)
+            .position(|(holds, breakpoint)| holds && !self.held.contains(breakpoint));
+        if let Some(index) = hit {
+            return Ok(Stop::Breakpoint(index));
+        }
+
+        let mut steps = 0;
+        loop {
+            if self.machine.is_halted() {
+                return Ok(Stop::Halted);
+            }
+            if max_steps == Some(steps) {
+                return Ok(Stop::Steps);
+            }
+            let held = self.holding_breakpoints();
+            if let Some(stop) = self.step()? {
+                return Ok(stop);
+            }
+            steps += 1;
+
+            let hit = self
+                .holding_breakpoints()
+                .into_iter()
+                .zip(held)
+                .zip(&self.breakpoints)
+                .position(|((holds, held), breakpoint)| match breakpoint {
+                    Breakpoint::Ip(_) => holds,
+                    Breakpoint::Register(..) => holds && !held,
+                });
+            if let Some(index) = hit {
+                return Ok(Stop::Breakpoint(index));
+            }
+        }
+    }
+
+    fn holding_breakpoints(&self) -> Vec<bool> {
+        let (ip, registers) = (self.machine.ip, &self.machine.registers);
+        self.breakpoints
+            .iter()
+            .map(|breakpoint| breakpoint.is_hit(ip, registers.as_ref()))
+            .collect()
+    }
+
+    fn print_state<O: Write>(&self, output: &mut O) -> Result<()> {
+        write!(output, "ip={} {:?}", self.machine.ip, self.machine.registers.as_ref())?;
+        match self.machine.program.instructions.get(self.machine.ip) {
+            Some(instruction) => writeln!(output, " next: {}", instruction)?,
+            None => writeln!(output, " halted")?,
+        }
+        Ok(())
+    }
+
+    fn print_stop<O: Write>(&self, stop: Stop, output: &mut O) -> Result<()> {
+        match stop {
+            Stop::Halted => writeln!(output, "halted after {} instructions", self.steps)?,
+            Stop::Breakpoint(index) => {
+                writeln!(output, "breakpoint {}: {}", index, self.breakpoints[index])?
+            }
+            Stop::Watch(register, old, new) => {
+                writeln!(output, "r{} changed from {} to {}", register, old, new)?
+            }
+            Stop::Steps => {}
+        }
+        self.print_state(output)
+    }
+
+    fn list<O: Write>(&self, output: &mut O, only_hit: bool) -> Result<()> {
+        let program = self.machine.program;
+        let mut order = (0..program.instructions.len()).collect::<Vec<_>>();
+        if only_hit {
+            order.retain(|&ip| self.hits[ip] > 0);
+            order.sort_by_key(|&ip| ::std::cmp::Reverse(self.hits[ip]));
+            order.truncate(10);
+        }
+        for ip in order {
+            let marker = if ip == self.machine.ip { "=>" } else { "  " };
+            let breakpoint = if self.breakpoints.contains(&Breakpoint::Ip(ip)) { "*" } else { " " };
+            writeln!(
+                output,
+                "{}{}{:>4} {:>12}  {}",
+                marker, breakpoint, ip, self.hits[ip], program.instructions[ip]
+            )?;
+        }
+        Ok(())
+    }
+
+    // Runs a single command, false once the debugger should quit
+    pub fn command<O: Write>(&mut self, line: &str, output: &mut O) -> Result<bool> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return Ok(true),
+        };
+        match (command, args) {
+            ("quit", []) | ("q", []) => return Ok(false),
+            ("help", []) | ("h", []) => writeln!(output, "{}", HELP)?,
+            ("step", _) | ("s", _) if args.len() <= 1 => {
+                let steps = match args.first() {
+                    Some(steps) => parse_number(steps)?,
+                    None => 1,
+                };
+                let stop = self.run(Some(steps))?;
+                self.print_stop(stop, output)?;
+            }
+            ("continue", []) | ("c", []) => {
+                let stop = self.run(None)?;
+                self.print_stop(stop, output)?;
+            }
+            ("break", _) | ("b", _) => {
+                self.add_breakpoint(args.join(" ").parse()?)?;
+                writeln!(output, "breakpoint {}", self.breakpoints.len() - 1)?;
+            }
+            ("breakpoints", []) => {
+                for (index, breakpoint) in self.breakpoints.iter().enumerate() {
+                    writeln!(output, "{}: {}", index, breakpoint)?;
+                }
+            }
+            ("delete", [index]) => {
+                let index: usize = parse_number(index)?;
+                if index >= self.breakpoints.len() {
+                    return Err(usage(format!("there is no breakpoint {}", index)));
+                }
+                self.breakpoints.remove(index);
+            }
+            ("watch", [register]) => self.watch(parse_register(register)?)?,
+            ("unwatch", [register]) => {
+                let register = parse_register(register)?;
+                self.watches.retain(|&watched| watched != register);
+            }
+            ("registers", []) | ("r", []) => self.print_state(output)?,
+            ("set", ["ip", ip]) => {
+                self.machine.ip = parse_number(ip)?;
+                self.print_state(output)?;
+            }
+            ("set", [register, value]) => {
+                let register = self.check_register(parse_register(register)?)?;
+                self.machine.registers.as_mut()[register] = parse_number(value)?;
+                self.print_state(output)?;
+            }
+            ("list", []) | ("l", []) => self.list(output, false)?,
+            ("hits", []) => self.list(output, true)?,
+            ("trace", ["off"]) => self.set_trace(None),
+            ("trace", ["-"]) => self.set_trace(Some(Box::new(io::stdout()))),
+            ("trace", [path]) => self.set_trace(Some(Box::new(File::create(path)?))),
+            ("reset", []) => {
+                self.reset();
+                self.print_state(output)?;
+            }
+            _ => return Err(usage(format!("unknown command {}, try help", line.trim()))),
+        }
+        Ok(true)
+    }
+
+    // Reads commands until quit or the end of the input. Mistakes in a command
+    // are reported without leaving the debugger.
+    pub fn repl<I: BufRead, O: Write>(&mut self, input: I, output: &mut O) -> Result<()> {
+        self.print_state(output)?;
+        let mut previous = String::new();
+        let mut lines = input.lines();
+        loop {
+            write!(output, "(elfcode) ")?;
+            output.flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => break,
+            };
+            let line = if line.trim().is_empty() {
+                previous.clone()
+            } else {
+                line
+            };
+            match self.command(&line, output) {
+                Ok(true) => previous = line,
+                Ok(false) => break,
+                Err(e) => writeln!(output, "{}", e.report())?,
+            }
+        }
+        writeln!(output)?;
+        Ok(())
+    }
+}
+
+// Debugs the program with as many registers as it uses
+pub fn debug<I: BufRead, O: Write>(program: &Program, input: I, output: &mut O) -> Result<()> {
+    let registers = vec![0; program.register_count()];
+    Debugger::with_registers(program, registers).repl(input, output)
+}
+
+#[test]
+fn debugger_test() {
+    let program = Program::from_str(
+        "#ip 0
+seti 5 0 1
+seti 6 0 2
+addi 0 1 0
+addr 1 2 3
+setr 1 0 0
+seti 8 0 4
+seti 9 0 5",
+    )
+    .unwrap();
+
+    let mut debugger = Debugger::<[Value; 6]>::new(&program);
+    debugger.add_breakpoint(Breakpoint::Ip(0)).unwrap();
+    assert_eq!(debugger.run(None).unwrap(), Stop::Breakpoint(0));
+    assert_eq!(debugger.steps, 0);
+    debugger.breakpoints.clear();
+    debugger.add_breakpoint(Breakpoint::Ip(4)).unwrap();
+    assert_eq!(debugger.run(None).unwrap(), Stop::Breakpoint(0));
+    assert_eq!(debugger.machine.registers, [3, 5, 6, 0, 0, 0]);
+    assert_eq!(debugger.hits, vec![1, 1, 1, 0, 0, 0, 0]);
+
+    // Continuing from a breakpoint does not stop at it again
+    debugger.watch(5).unwrap();
+    assert_eq!(debugger.run(None).unwrap(), Stop::Watch(5, 0, 9));
+    assert_eq!(debugger.run(None).unwrap(), Stop::Halted);
+    assert_eq!(debugger.steps, 5);
+
+    debugger.reset();
+    debugger.breakpoints.clear();
+    debugger.add_breakpoint("r2 >= 6".parse().unwrap()).unwrap();
+    assert_eq!(debugger.run(None).unwrap(), Stop::Breakpoint(0));
+    assert_eq!(debugger.machine.ip, 2);
+    assert_eq!(debugger.run(Some(1)).unwrap(), Stop::Steps);
+    debugger.machine.registers[2] = 0;
+    assert_eq!(debugger.run(Some(1)).unwrap(), Stop::Steps);
+    assert!(debugger.add_breakpoint("r6 == 1".parse().unwrap()).is_err());
+    assert!("r1 =< 2".parse::<Breakpoint>().is_err());
+
+    let commands = "set r0 0\nstep 2\n\nbogus\nhits\nquit\nstep\n";
+    let mut output = Vec::new();
+    debug(&program, commands.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("ip=2 [1, 5, 6, 0, 0, 0] next: addi 0 1 0"));
+    assert!(output.contains("ip=6 [5, 5, 6, 0, 0, 0] next: seti 9 0 5"));
+    assert!(output.contains("unknown command bogus"));
+    assert!(output.contains("      4            1  setr 1 0 0"));
+}
@@ -0,0 +1,660 @@
+// Decompiler that lifts ElfCode programs to Rust-like pseudocode. Writes to the
+// instruction pointer register become jumps, jumps back become loops and jumps
+// over code become ifs. Whatever does not fit these shapes stays a goto.
+use crate::elfcode::{Instruction, Opcode, Operand, Program, Value};
+use std::fmt::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term {
+    Register(Value),
+    Constant(Value),
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Term::Register(register) => write!(f, "r{}", register),
+            Term::Constant(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+// The value an instruction computes, where reading the instruction pointer
+// register reads the address of the instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expr {
+    pub opcode: Opcode,
+    pub a: Term,
+    pub b: Term,
+}
+
+fn operator(opcode: Opcode) -> Option<&'static str> {
+    match opcode {
+        Opcode::Addr | Opcode::Addi => Some("+"),
+        Opcode::Mulr | Opcode::Muli => Some("*"),
+        Opcode::Banr | Opcode::Bani => Some("&"),
+        Opcode::Borr | Opcode::Bori => Some("|"),
+        Opcode::Setr | Opcode::Seti => None,
+        Opcode::Gtir | Opcode::Gtri | Opcode::Gtrr => Some(">"),
+        Opcode::Eqir | Opcode::Eqri | Opcode::Eqrr => Some("=="),
+    }
+}
+
+fn is_comparison(opcode: Opcode) -> bool {
+    match opcode {
+        Opcode::Gtir | Opcode::Gtri | Opcode::Gtrr => true,
+        Opcode::Eqir | Opcode::Eqri | Opcode::Eqrr => true,
+        _ => false,
+    }
+}
+
+impl Expr {
+    fn lift(instruction: &Instruction, ip_register: Option<usize>, address: usize) -> Expr {
+        let term = |operand, value: Value| match operand {
+            Operand::Register if value >= 0 && Some(value as usize) == ip_register => {
+                Term::Constant(address as Value)
+            }
+            Operand::Register => Term::Register(value),
+            Operand::Immediate | Operand::Ignored => Term::Constant(value),
+        };
+        let (a, b) = instruction.opcode.operands();
+        let expr = Expr {
+            opcode: instruction.opcode,
+            a: term(a, instruction.a),
+            b: term(b, instruction.b),
+        };
+        match (expr.a, expr.b) {
+            (Term::Constant(a), Term::Constant(b)) => Expr::constant(expr.opcode.apply(a, b)),
+            _ => expr,
+        }
+    }
+
+    fn constant(value: Value) -> Expr {
+        Expr {
+            opcode: Opcode::Seti,
+            a: Term::Constant(value),
+            b: Term::Constant(0),
+        }
+    }
+
+    fn registers(&self) -> u64 {
+        let (a, b) = self.opcode.operands();
+        let mut registers = 0;
+        for &(operand, term) in &[(a, self.a), (b, self.b)] {
+            if let (Operand::Register, Term::Register(register)) = (operand, term) {
+                registers |= bit(register);
+            }
+        }
+        registers
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match operator(self.opcode) {
+            None => write!(f, "{}", self.a),
+            Some(operator) if is_comparison(self.opcode) => {
+                write!(f, "({} {} {}) as i64", self.a, operator, self.b)
+            }
+            Some(operator) => write!(f, "{} {} {}", self.a, operator, self.b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub comparison: Expr,
+    pub negated: bool,
+}
+
+impl Condition {
+    // Holds when the register is not zero
+    fn flag(register: Value) -> Condition {
+        Condition {
+            comparison: Expr {
+                opcode: Opcode::Eqri,
+                a: Term::Register(register),
+                b: Term::Constant(0),
+            },
+            negated: true,
+        }
+    }
+
+    fn negate(self) -> Condition {
+        Condition {
+            negated: !self.negated,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operator = match (operator(self.comparison.opcode), self.negated) {
+            (Some(">"), false) => ">",
+            (Some(">"), true) => "<=",
+            (_, false) => "==",
+            (_, true) => "!=",
+        };
+        write!(
+            f,
+            "{} {} {}",
+            self.comparison.a, operator, self.comparison.b
+        )
+    }
+}
+
+// Where execution continues after an instruction. Addresses outside of the
+// program halt it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    Jump(Value),
+    Branch(Condition, Value, Value), // to the first address when the condition holds
+    Computed(Expr),                  // to the value plus one
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lifted {
+    pub assignment: Option<(Value, Expr)>,
+    pub flow: Flow,
+}
+
+fn bit(register: Value) -> u64 {
+    if register >= 0 && register < 64 {
+        1 << register
+    } else {
+        0
+    }
+}
+
+// The puzzles read their answer from register 0 once the program halted
+const LIVE_AT_EXIT: u64 = 1;
+
+// Lifts every instruction on its own. Adding a register to the instruction
+// pointer right after a comparison into that register skips the next
+// instruction when the comparison holds, anything else added is a computed jump.
+pub fn lift(program: &Program) -> Vec<Lifted> {
+    let mut lifted = Vec::<Lifted>::with_capacity(program.instructions.len());
+    for (address, instruction) in program.instructions.iter().enumerate() {
+        let expr = Expr::lift(instruction, program.ip_register, address);
+        let writes_ip = instruction.c >= 0 && Some(instruction.c as usize) == program.ip_register;
+        if !writes_ip {
+            lifted.push(Lifted {
+                assignment: Some((instruction.c, expr)),
+                flow: Flow::Next,
+            });
+            continue;
+        }
+
+        let flag = |register| match lifted.last() {
+            Some(Lifted {
+                assignment: Some((written, previous)),
+                ..
+            }) => *written == register && is_comparison(previous.opcode),
+            _ => false,
+        };
+        let flow = match (expr.opcode, expr.a, expr.b) {
+            (Opcode::Seti, Term::Constant(target), _) => Flow::Jump(target.wrapping_add(1)),
+            (Opcode::Addr, Term::Constant(base), Term::Register(register))
+            | (Opcode::Addr, Term::Register(register), Term::Constant(base))
+                if flag(register) =>
+            {
+                Flow::Branch(Condition::flag(register), base.wrapping_add(2), base.wrapping_add(1))
+            }
+            _ => Flow::Computed(expr),
+        };
+        lifted.push(Lifted {
+            assignment: None,
+            flow,
+        });
+    }
+    lifted
+}
+
+fn address(lifted: &[Lifted], target: Value) -> Option<usize> {
+    if target >= 0 && (target as usize) < lifted.len() {
+        Some(target as usize)
+    } else {
+        None
+    }
+}
+
+// The addresses execution can continue at after an instruction, None for
+// unknown targets
+fn successors(lifted: &[Lifted], at: usize) -> Option<Vec<Value>> {
+    match lifted[at].flow {
+        Flow::Next => Some(vec![at as Value + 1]),
+        Flow::Jump(target) => Some(vec![target]),
+        Flow::Branch(_, taken, skipped) => Some(vec![taken, skipped]),
+        Flow::Computed(_) => None,
+    }
+}
+
+// The registers that may still be read after every instruction
+fn live_out(lifted: &[Lifted]) -> Vec<u64> {
+    let mut live_in = vec![0u64; lifted.len()];
+    let mut live_out = vec![0u64; lifted.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for at in (0..lifted.len()).rev() {
+            let out = match successors(lifted, at) {
+                Some(targets) => targets.iter().fold(0, |live, &target| {
+                    live | address(lifted, target).map_or(LIVE_AT_EXIT, |to| live_in[to])
+                }),
+                None => !0,
+            };
+            let (defined, used) = match lifted[at] {
+                Lifted {
+                    assignment: Some((register, expr)),
+                    ..
+                } => (bit(register), expr.registers()),
+                Lifted {
+                    flow: Flow::Branch(condition, _, _),
+                    ..
+                } => (0, condition.comparison.registers()),
+                Lifted {
+                    flow: Flow::Computed(expr),
+                    ..
+                } => (0, expr.registers()),
+                _ => (0, 0),
+            };
+            let input = used | (out & !defined);
+            changed |= out != live_out[at] || input != live_in[at];
+            live_out[at] = out;
+            live_in[at] = input;
+        }
+    }
+    live_out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Label(usize),
+    Assign(Value, Expr),
+    If(Condition, Vec<Statement>, Vec<Statement>),
+    Loop(Vec<Statement>),
+    Break,
+    Continue,
+    Goto(usize),
+    Computed(Expr),
+    Halt,
+}
+
+struct Structurer<'a> {
+    lifted: &'a [Lifted],
+    starts: Vec<usize>,         // the first address of every basic block
+    loops: Vec<(usize, usize)>, // the header and exit address of the enclosing loops
+}
+
+impl<'a> Structurer<'a> {
+    fn end(&self, block: usize) -> usize {
+        self.starts
+            .get(block + 1)
+            .cloned()
+            .unwrap_or_else(|| self.lifted.len())
+    }
+
+    fn block_at(&self, target: Value) -> Option<usize> {
+        let at = address(self.lifted, target)?;
+        self.starts.binary_search(&at).ok()
+    }
+
+    fn jumps_to(&self, block: usize, start: usize) -> bool {
+        match self.lifted[self.end(block) - 1].flow {
+            Flow::Jump(target) => target == start as Value,
+            Flow::Branch(_, taken, skipped) => taken == start as Value || skipped == start as Value,
+            _ => false,
+        }
+    }
+
+    // What a jump turns into when the code after the jump continues at `next`
+    fn jump(&self, target: Value, next: usize) -> Option<Statement> {
+        if target == next as Value {
+            return None;
+        }
+        let to = match address(self.lifted, target) {
+            Some(to) => to,
+            None if next >= self.lifted.len() => return None,
+            None => return Some(Statement::Halt),
+        };
+        match self.loops.last() {
+            Some(&(header, _)) if header == to => Some(Statement::Continue),
+            Some(&(_, exit)) if exit == to => Some(Statement::Break),
+            _ => Some(Statement::Goto(to)),
+        }
+    }
+
+    // Structures the blocks from `first` up to `last`, after which execution
+    // continues at `next`. The loop at `first` is already being built when
+    // `in_loop` is set.
+    fn region(&mut self, first: usize, last: usize, next: usize, in_loop: bool) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        let mut block = first;
+        while block < last {
+            let start = self.starts[block];
+            if !(in_loop && block == first) {
+                statements.push(Statement::Label(start));
+                if let Some(back) = (block..last).rev().find(|&from| self.jumps_to(from, start)) {
+                    self.loops.push((start, self.end(back)));
+                    let body = self.region(block, back + 1, start, true);
+                    self.loops.pop();
+                    statements.push(Statement::Loop(body));
+                    block = back + 1;
+                    continue;
+                }
+            }
+
+            let end = self.end(block);
+            for lifted in &self.lifted[start..end] {
+                if let Some((register, expr)) = lifted.assignment {
+                    statements.push(Statement::Assign(register, expr));
+                }
+            }
+            let after = if block + 1 < last { end } else { next };
+            match self.lifted[end - 1].flow {
+                Flow::Next => statements.extend(self.jump(end as Value, after)),
+                Flow::Jump(target) => statements.extend(self.jump(target, after)),
+                Flow::Computed(expr) => statements.push(Statement::Computed(expr)),
+                Flow::Branch(condition, taken, skipped) => {
+                    if let Some((statement, resume)) =
+                        self.structure_if(block, last, next, condition, taken, skipped)
+                    {
+                        statements.push(statement);
+                        block = resume;
+                        continue;
+                    }
+                    let then = self.jump(taken, after).into_iter().collect();
+                    let otherwise = self.jump(skipped, after).into_iter().collect();
+                    statements.push(Statement::If(condition, then, otherwise));
+                }
+            }
+            block += 1;
+        }
+        statements
+    }
+
+    // A branch to the next block or further down in the region becomes an if,
+    // with an else when the code it skips ends by jumping over more code.
+    // Returns the block after the if.
+    fn structure_if(
+        &mut self,
+        block: usize,
+        last: usize,
+        next: usize,
+        condition: Condition,
+        taken: Value,
+        skipped: Value,
+    ) -> Option<(Statement, usize)> {
+        let fall = self.end(block) as Value;
+        let (condition, over) = if skipped == fall {
+            (condition.negate(), taken)
+        } else if taken == fall {
+            (condition, skipped)
+        } else {
+            return None;
+        };
+        let within = |this: &Self, target: Value| match this.block_at(target) {
+            Some(to) if to < last || (to == last && target == next as Value) => Some(to),
+            _ => None,
+        };
+        let skip_to = match within(self, over) {
+            Some(skip_to) if over > fall => skip_to,
+            _ => return None,
+        };
+
+        let else_to = match self.lifted[self.end(skip_to - 1) - 1].flow {
+            Flow::Jump(target) if target > over => within(self, target).map(|to| (to, target)),
+            _ => None,
+        };
+        Some(match else_to {
+            Some((else_to, target)) => {
+                let then = self.region(block + 1, skip_to, target as usize, false);
+                let otherwise = self.region(skip_to, else_to, target as usize, false);
+                (Statement::If(condition, then, otherwise), else_to)
+            }
+            None => {
+                let then = self.region(block + 1, skip_to, over as usize, false);
+                (Statement::If(condition, then, Vec::new()), skip_to)
+            }
+        })
+    }
+}
+
+// Folds comparisons into the branches that test them, when nothing else reads
+// the register they were stored in
+fn fold_conditions(lifted: &mut [Lifted], starts: &[usize]) {
+    let live_out = live_out(lifted);
+    for at in 1..lifted.len() {
+        if starts.binary_search(&at).is_ok() {
+            continue;
+        }
+        let (register, comparison) = match lifted[at - 1].assignment {
+            Some((register, expr)) if is_comparison(expr.opcode) => (register, expr),
+            _ => continue,
+        };
+        if let Flow::Branch(condition, taken, skipped) = lifted[at].flow {
+            if condition == Condition::flag(register) && live_out[at] & bit(register) == 0 {
+                let condition = Condition {
+                    comparison,
+                    negated: false,
+                };
+                lifted[at].flow = Flow::Branch(condition, taken, skipped);
+                lifted[at - 1].assignment = None;
+            }
+        }
+    }
+}
+
+fn labels(statements: &[Statement], targets: &mut Vec<usize>) {
+    for statement in statements {
+        match statement {
+            Statement::Goto(target) => targets.push(*target),
+            Statement::If(_, then, otherwise) => {
+                labels(then, targets);
+                labels(otherwise, targets);
+            }
+            Statement::Loop(body) => labels(body, targets),
+            _ => {}
+        }
+    }
+}
+
+pub fn structure(program: &Program) -> Vec<Statement> {
+    let mut lifted = lift(program);
+    if lifted.is_empty() {
+        return Vec::new();
+    }
+
+    let mut starts = vec![0];
+    for (at, instruction) in lifted.iter().enumerate() {
+        if instruction.flow != Flow::Next {
+            starts.push(at + 1);
+        }
+        if let Flow::Jump(_) | Flow::Branch(..) = instruction.flow {
+            for target in successors(&lifted, at).unwrap_or_default() {
+                starts.extend(address(&lifted, target));
+            }
+        }
+    }
+    starts.retain(|&start| start < lifted.len());
+    starts.sort();
+    starts.dedup();
+
+    fold_conditions(&mut lifted, &starts);
+    let mut structurer = Structurer {
+        lifted: &lifted,
+        starts,
+        loops: Vec::new(),
+    };
+    let statements = structurer.region(0, structurer.starts.len(), lifted.len(), false);
+
+    // Computed jumps keep the labels they may jump to, which are usually close
+    // behind them
+    let mut targets = Vec::new();
+    labels(&statements, &mut targets);
+    for (at, instruction) in lifted.iter().enumerate() {
+        if let Flow::Computed(expr) = instruction.flow {
+            let after = match (expr.opcode, expr.a, expr.b) {
+                (Opcode::Addr, Term::Constant(base), _)
+                | (Opcode::Addr, _, Term::Constant(base)) => base.max(at as Value) as usize,
+                _ => 0,
+            };
+            targets.extend(structurer.starts.iter().filter(|&&start| start > after));
+        }
+    }
+    simplify(statements, &targets)
+}
+
+// Removes the labels nothing jumps to, and the branches of ifs that ended up
+// empty
+fn simplify(statements: Vec<Statement>, targets: &[usize]) -> Vec<Statement> {
+    let mut simplified = Vec::new();
+    for statement in statements {
+        match statement {
+            Statement::Label(at) if !targets.contains(&at) => {}
+            Statement::If(condition, then, otherwise) => {
+                let then = simplify(then, targets);
+                let otherwise = simplify(otherwise, targets);
+                match (then.is_empty(), otherwise.is_empty()) {
+                    (true, true) => {}
+                    (true, false) => {
+                        simplified.push(Statement::If(condition.negate(), otherwise, then))
+                    }
+                    _ => simplified.push(Statement::If(condition, then, otherwise)),
+                }
+            }
+            Statement::Loop(body) => simplified.push(Statement::Loop(simplify(body, targets))),
+            statement => simplified.push(statement),
+        }
+    }
+    simplified
+}
+
+fn write_statements(output: &mut String, statements: &[Statement], depth: usize) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    for statement in statements {
+        match statement {
+            Statement::Label(at) => writeln!(output, "{}L{}:", indent, at)?,
+            Statement::Assign(register, expr) => {
+                let target = Term::Register(*register);
+                match operator(expr.opcode) {
+                    Some(operator) if !is_comparison(expr.opcode) && expr.a == target => {
+                        writeln!(output, "{}{} {}= {};", indent, target, operator, expr.b)?
+                    }
+                    Some(operator) if !is_comparison(expr.opcode) && expr.b == target => {
+                        writeln!(output, "{}{} {}= {};", indent, target, operator, expr.a)?
+                    }
+                    _ => writeln!(output, "{}{} = {};", indent, target, expr)?,
+                }
+            }
+            Statement::If(condition, then, otherwise) => {
+                writeln!(output, "{}if {} {{", indent, condition)?;
+                write_statements(output, then, depth + 1)?;
+                if !otherwise.is_empty() {
+                    writeln!(output, "{}}} else {{", indent)?;
+                    write_statements(output, otherwise, depth + 1)?;
+                }
+                writeln!(output, "{}}}", indent)?;
+            }
+            Statement::Loop(body) => {
+                writeln!(output, "{}loop {{", indent)?;
+                write_statements(output, body, depth + 1)?;
+                writeln!(output, "{}}}", indent)?;
+            }
+            Statement::Break => writeln!(output, "{}break;", indent)?,
+            Statement::Continue => writeln!(output, "{}continue;", indent)?,
+            Statement::Goto(at) => writeln!(output, "{}goto L{};", indent, at)?,
+            Statement::Computed(expr) => writeln!(output, "{}goto L[{} + 1];", indent, expr)?,
+            Statement::Halt => writeln!(output, "{}return;", indent)?,
+        }
+    }
+    Ok(())
+}
+
+pub fn decompile(program: &Program) -> String {
+    let mut output = String::new();
+    if let Some(ip_register) = program.ip_register {
+        output.push_str(&format!("// r{} is the instruction pointer\n", ip_register));
+    }
+    write_statements(&mut output, &structure(program), 0).expect("writing to a string");
+    output
+}
+
+#[test]
+fn decompiler_test() {
+    use std::str::FromStr;
+
+    let program = Program::from_str(
+        "#ip 3
+seti 1 0 4
+seti 1 7 2
+mulr 4 2 1
+eqrr 1 5 1
+addr 1 3 3
+addi 3 1 3
+addr 4 0 0
+addi 2 1 2
+gtrr 2 5 1
+addr 3 1 3
+seti 1 3 3
+addi 4 1 4
+gtrr 4 5 1
+addr 1 3 3
+seti 0 8 3
+mulr 3 3 3",
+    )
+    .unwrap();
+    assert_eq!(
+        decompile(&program),
+        "// r3 is the instruction pointer
+r4 = 1;
+loop {
+    r2 = 1;
+    loop {
+        r1 = r4 * r2;
+        if r1 == r5 {
+            r0 += r4;
+        }
+        r2 += 1;
+        if r2 > r5 {
+            break;
+        }
+    }
+    r4 += 1;
+    if r4 > r5 {
+        break;
+    }
+}
+"
+    );
+
+    // The flag is read again after the branch, so it is kept
+    let program = Program::from_str(
+        "#ip 0
+gtri 1 5 2
+addr 0 2 0
+seti 7 0 1
+addr 2 1 1
+addr 2 0 0
+addi 1 1 1",
+    )
+    .unwrap();
+    assert_eq!(
+        decompile(&program),
+        "// r0 is the instruction pointer
+r2 = (r1 > 5) as i64;
+if r2 == 0 {
+    r1 = 7;
+}
+r1 += r2;
+goto L[r2 + 4 + 1];
+L5:
+r1 += 1;
+"
+    );
+
+    let program = Program::from_str("#ip 0\nseti 9223372036854775807 0 0").unwrap();
+    decompile(&program);
+}
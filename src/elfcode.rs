@@ -0,0 +1,428 @@
+// ElfCode, the assembly language of days 16, 19 and 21, and a machine to run it
+use crate::error::ResultExt;
+use crate::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+pub type Value = i64;
+
+// Any number of registers, like the 4 of day 16 or the 6 of day 19
+pub trait Registers: Clone + Default + AsRef<[Value]> + AsMut<[Value]> {}
+impl<R: Clone + Default + AsRef<[Value]> + AsMut<[Value]>> Registers for R {}
+
+// How an opcode reads one of its operands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register,
+    Immediate,
+    Ignored,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Opcode {
+    Addr,
+    Addi,
+    Mulr,
+    Muli,
+    Banr,
+    Bani,
+    Borr,
+    Bori,
+    Setr,
+    Seti,
+    Gtir,
+    Gtri,
+    Gtrr,
+    Eqir,
+    Eqri,
+    Eqrr,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 16] = [
+        Opcode::Addr,
+        Opcode::Addi,
+        Opcode::Mulr,
+        Opcode::Muli,
+        Opcode::Banr,
+        Opcode::Bani,
+        Opcode::Borr,
+        Opcode::Bori,
+        Opcode::Setr,
+        Opcode::Seti,
+        Opcode::Gtir,
+        Opcode::Gtri,
+        Opcode::Gtrr,
+        Opcode::Eqir,
+        Opcode::Eqri,
+        Opcode::Eqrr,
+    ];
+
+    #[rustfmt::skip]
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Addr => "addr",
+            Opcode::Addi => "addi",
+            Opcode::Mulr => "mulr",
+            Opcode::Muli => "muli",
+            Opcode::Banr => "banr",
+            Opcode::Bani => "bani",
+            Opcode::Borr => "borr",
+            Opcode::Bori => "bori",
+            Opcode::Setr => "setr",
+            Opcode::Seti => "seti",
+            Opcode::Gtir => "gtir",
+            Opcode::Gtri => "gtri",
+            Opcode::Gtrr => "gtrr",
+            Opcode::Eqir => "eqir",
+            Opcode::Eqri => "eqri",
+            Opcode::Eqrr => "eqrr",
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+        Opcode::ALL
+            .iter()
+            .cloned()
+            .find(|opcode| opcode.mnemonic() == mnemonic)
+    }
+
+    #[rustfmt::skip]
+    pub fn operands(self) -> (Operand, Operand) {
+        use self::Operand::*;
+        match self {
+            Opcode::Addr => (Register,  Register ),
+            Opcode::Addi => (Register,  Immediate),
+            Opcode::Mulr => (Register,  Register ),
+            Opcode::Muli => (Register,  Immediate),
+            Opcode::Banr => (Register,  Register ),
+            Opcode::Bani => (Register,  Immediate),
+            Opcode::Borr => (Register,  Register ),
+            Opcode::Bori => (Register,  Immediate),
+            Opcode::Setr => (Register,  Ignored  ),
+            Opcode::Seti => (Immediate, Ignored  ),
+            Opcode::Gtir => (Immediate, Register ),
+            Opcode::Gtri => (Register,  Immediate),
+            Opcode::Gtrr => (Register,  Register ),
+            Opcode::Eqir => (Immediate, Register ),
+            Opcode::Eqri => (Register,  Immediate),
+            Opcode::Eqrr => (Register,  Register ),
+        }
+    }
+
+    // The result for the values of both operands
+    #[rustfmt::skip]
+    pub fn apply(self, a: Value, b: Value) -> Value {
+        match self {
+            Opcode::Addr | Opcode::Addi => a.wrapping_add(b),
+            Opcode::Mulr | Opcode::Muli => a.wrapping_mul(b),
+            Opcode::Banr | Opcode::Bani => a & b,
+            Opcode::Borr | Opcode::Bori => a | b,
+            Opcode::Setr | Opcode::Seti => a,
+            Opcode::Gtir | Opcode::Gtri | Opcode::Gtrr => if a > b { 1 } else { 0 },
+            Opcode::Eqir | Opcode::Eqri | Opcode::Eqrr => if a == b { 1 } else { 0 },
+        }
+    }
+}
+
+fn register_index(registers: &[Value], register: Value) -> Result<usize> {
+    if register >= 0 && (register as usize) < registers.len() {
+        Ok(register as usize)
+    } else {
+        Err(Error::Input("register out of range"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub a: Value,
+    pub b: Value,
+    pub c: Value, // the register that receives the result
+}
+
+impl Instruction {
+    pub fn new(opcode: Opcode, a: Value, b: Value, c: Value) -> Instruction {
+        Instruction { opcode, a, b, c }
+    }
+
+    fn operand(registers: &[Value], operand: Operand, value: Value) -> Result<Value> {
+        match operand {
+            Operand::Register => Ok(registers[register_index(registers, value)?]),
+            Operand::Immediate => Ok(value),
+            Operand::Ignored => Ok(0),
+        }
+    }
+
+    pub fn execute(&self, registers: &mut [Value]) -> Result<()> {
+        let (a, b) = self.opcode.operands();
+        let a = Instruction::operand(registers, a, self.a)?;
+        let b = Instruction::operand(registers, b, self.b)?;
+        registers[register_index(registers, self.c)?] = self.opcode.apply(a, b);
+        Ok(())
+    }
+
+    // Renumbers every register the instruction uses, immediates stay the same
+    pub fn map_registers<F: FnMut(Value) -> Value>(&self, mut f: F) -> Instruction {
+        let (a, b) = self.opcode.operands();
+        Instruction {
+            opcode: self.opcode,
+            a: if a == Operand::Register { f(self.a) } else { self.a },
+            b: if b == Operand::Register { f(self.b) } else { self.b },
+            c: f(self.c),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.opcode.mnemonic(), self.a, self.b, self.c)
+    }
+}
+
+fn parse_operands(operands: &[&str]) -> Result<(Value, Value, Value)> {
+    match operands {
+        [a, b, c] => Ok((a.parse()?, b.parse()?, c.parse()?)),
+        _ => Err(Error::Input("expected three operands")),
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Instruction> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        let (mnemonic, operands) = words
+            .split_first()
+            .ok_or(Error::Input("expected an instruction"))?;
+        let opcode = Opcode::from_mnemonic(mnemonic).ok_or(Error::Input("unknown opcode"))?;
+        let (a, b, c) = parse_operands(operands)?;
+        Ok(Instruction { opcode, a, b, c })
+    }
+}
+
+// An instruction in the encoding of day 16, where opcodes are numbers whose
+// meaning has to be deduced first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawInstruction {
+    pub number: Value,
+    pub a: Value,
+    pub b: Value,
+    pub c: Value,
+}
+
+impl RawInstruction {
+    pub fn decode(&self, opcode: Opcode) -> Instruction {
+        Instruction::new(opcode, self.a, self.b, self.c)
+    }
+}
+
+impl fmt::Display for RawInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} {}", self.number, self.a, self.b, self.c)
+    }
+}
+
+impl FromStr for RawInstruction {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<RawInstruction> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        let (number, operands) = words
+            .split_first()
+            .ok_or(Error::Input("expected an instruction"))?;
+        let (a, b, c) = parse_operands(operands)?;
+        Ok(RawInstruction {
+            number: number.parse()?,
+            a,
+            b,
+            c,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    pub ip_register: Option<usize>, // bound to the instruction pointer by #ip
+    pub instructions: Vec<Instruction>,
+}
+
+impl Program {
+    // `opcodes[n]` is the opcode numbered n
+    pub fn decode(instructions: &[RawInstruction], opcodes: &[Opcode]) -> Result<Program> {
+        let instructions = instructions
+            .iter()
+            .map(|raw| {
+                if raw.number < 0 || raw.number as usize >= opcodes.len() {
+                    return Err(Error::Input("unknown opcode number"));
+                }
+                Ok(raw.decode(opcodes[raw.number as usize]))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Program {
+            ip_register: None,
+            instructions,
+        })
+    }
+
+    // The number of registers the program touches, counting the #ip one
+    pub fn register_count(&self) -> usize {
+        let mut count = self.ip_register.map_or(0, |ip_register| ip_register + 1);
+        for instruction in &self.instructions {
+            let (a, b) = instruction.opcode.operands();
+            let c = Operand::Register;
+            for &(operand, value) in &[(a, instruction.a), (b, instruction.b), (c, instruction.c)] {
+                if operand == Operand::Register && value >= 0 {
+                    count = count.max(value as usize + 1);
+                }
+            }
+        }
+        count
+    }
+}
+
+// Disassembles the program into the text it was assembled from
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut separator = "";
+        if let Some(ip_register) = self.ip_register {
+            write!(f, "#ip {}", ip_register)?;
+            separator = "\n";
+        }
+        for instruction in &self.instructions {
+            write!(f, "{}{}", separator, instruction)?;
+            separator = "\n";
+        }
+        Ok(())
+    }
+}
+
+// Assembles the text of a program, one instruction per line after an optional
+// #ip directive
+impl FromStr for Program {
+    type Err = Error;
+    fn from_str(input: &str) -> Result<Program> {
+        let mut program = Program::default();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("#ip ") {
+                if i > 0 {
+                    return Err(Error::Input("#ip must be the first line")).at_line(i + 1, line);
+                }
+                let ip_register: Result<usize> = line[4..].trim().parse().map_err(Error::from);
+                program.ip_register = Some(ip_register.at_line(i + 1, line)?);
+                continue;
+            }
+            let instruction = Instruction::from_str(line).at_line(i + 1, line)?;
+            program.instructions.push(instruction);
+        }
+        Ok(program)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machine<'a, R> {
+    pub program: &'a Program,
+    pub registers: R,
+    pub ip: usize,
+}
+
+impl<'a, R: Registers> Machine<'a, R> {
+    pub fn new(program: &'a Program) -> Machine<'a, R> {
+        Machine::with_registers(program, R::default())
+    }
+
+    pub fn with_registers(program: &'a Program, registers: R) -> Machine<'a, R> {
+        Machine {
+            program,
+            registers,
+            ip: 0,
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.ip >= self.program.instructions.len()
+    }
+
+    // Executes the next instruction, false once the program has halted
+    pub fn step(&mut self) -> Result<bool> {
+        let instruction = match self.program.instructions.get(self.ip) {
+            Some(instruction) => instruction,
+            None => return Ok(false),
+        };
+        let registers = self.registers.as_mut();
+        match self.program.ip_register {
+            Some(ip_register) => {
+                let ip_register = register_index(registers, ip_register as Value)?;
+                registers[ip_register] = self.ip as Value;
+                instruction.execute(registers)?;
+                // Jumping before the first instruction halts the program as well
+                let ip = registers[ip_register] + 1;
+                self.ip = if ip < 0 { usize::max_value() } else { ip as usize };
+            }
+            None => {
+                instruction.execute(registers)?;
+                self.ip += 1;
+            }
+        }
+        Ok(true)
+    }
+
+    // Runs until the program halts, and returns how many instructions it executed
+    pub fn run(&mut self) -> Result<usize> {
+        let mut count = 0;
+        while self.step()? {
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[test]
+fn elfcode_test() {
+    const PROGRAM: &str = "#ip 0
+seti 5 0 1
+seti 6 0 2
+addi 0 1 0
+addr 1 2 3
+setr 1 0 0
+seti 8 0 4
+seti 9 0 5";
+
+    let program = Program::from_str(PROGRAM).unwrap();
+    assert_eq!(program.ip_register, Some(0));
+    assert_eq!(program.instructions[2], Instruction::new(Opcode::Addi, 0, 1, 0));
+    assert_eq!(program.to_string(), PROGRAM);
+    assert_eq!(program.register_count(), 6);
+
+    let mut machine = Machine::<[Value; 6]>::new(&program);
+    assert_eq!(machine.run().unwrap(), 5);
+    assert!(machine.is_halted());
+    assert_eq!(machine.registers, [6, 5, 6, 0, 0, 9]);
+
+    // Without #ip, instructions run in order
+    let opcodes = [Opcode::Seti, Opcode::Mulr, Opcode::Gtir];
+    let raw = ["0 7 0 1", "1 1 1 2", "2 50 2 3"]
+        .iter()
+        .map(|line| line.parse::<RawInstruction>().unwrap())
+        .collect::<Vec<_>>();
+    let program = Program::decode(&raw, &opcodes).unwrap();
+    assert_eq!(program.to_string(), "seti 7 0 1\nmulr 1 1 2\ngtir 50 2 3");
+    assert_eq!(program.register_count(), 4);
+    let mut machine = Machine::with_registers(&program, vec![0; program.register_count()]);
+    machine.run().unwrap();
+    assert_eq!(machine.registers, [0, 7, 49, 1]);
+
+    let mut registers = [3, 2, 1, 1];
+    Instruction::new(Opcode::Mulr, 2, 1, 2).execute(&mut registers).unwrap();
+    assert_eq!(registers, [3, 2, 2, 1]);
+    assert!(Instruction::new(Opcode::Addr, 4, 0, 0).execute(&mut registers).is_err());
+    assert!(Program::decode(&raw, &opcodes[..2]).is_err());
+    assert!(Program::from_str("addi 1 2").is_err());
+    assert!(Program::from_str("jmp 1 2 3").is_err());
+    assert_eq!(
+        Instruction::new(Opcode::Gtir, 5, 1, 2).map_registers(|r| r + 1),
+        Instruction::new(Opcode::Gtir, 5, 2, 3)
+    );
+}
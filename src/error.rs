@@ -1,3 +1,5 @@
+use std::fmt;
+
 pub type Result<T> = ::std::result::Result<T, Error>;
 #[derive(Debug)]
 pub enum Error {
@@ -8,17 +10,218 @@ pub enum Error {
     Json(::serde_json::Error),
     ParseInt(::std::num::ParseIntError),
 
+    Usage(String),
     DayDoesNotExist(String),
+    MissingPart(&'static str),
+    InputNotCached(String),
+    Cache(&'static str),
+    Config(&'static str),
+    InvalidFile(String, String), // the file name and what is wrong with it
+    VerificationFailed(usize),
+    PartsFailed(usize),
     MissingSessionToken,
     InvalidSessionToken(::reqwest::StatusCode),
+    DayNotUnlocked(String),
+    ServerError(::reqwest::StatusCode),
+    UnexpectedStatus(::reqwest::StatusCode),
+    Offline(String),
     Input(&'static str),
+    MissingExample(String, String),
+    AnswerRejected(String),
+    UnexpectedResponse,
+    Timeout(::std::time::Duration),
+    StillRunning(usize),
+    Panicked(String),
+    Located(Location, Box<Error>),
+    Part(String, &'static str, Box<Error>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,           // 1-based
+    pub column: Option<usize>, // 1-based, if known
+    pub snippet: String,
+}
+
+impl Location {
+    pub fn line(line: usize, snippet: &str) -> Location {
+        Location {
+            line,
+            column: None,
+            snippet: snippet.to_owned(),
+        }
+    }
+
+    // For grids, where the error is in a single cell of a line
+    pub fn cell(line: usize, column: usize, snippet: &str) -> Location {
+        Location {
+            line,
+            column: Some(column),
+            snippet: snippet.to_owned(),
+        }
+    }
+
+    pub fn at_offset(input: &str, offset: usize) -> Location {
+        let line_start = input[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = input[offset..]
+            .find('\n')
+            .map(|i| offset + i)
+            .unwrap_or(input.len());
+        Location {
+            line: input[..line_start].matches('\n').count() + 1,
+            column: Some(input[line_start..offset].chars().count() + 1),
+            snippet: input[line_start..line_end].trim_end_matches('\r').to_owned(),
+        }
+    }
+}
+
+pub trait ResultExt<T> {
+    fn at(self, location: Location) -> Result<T>;
+
+    fn at_line(self, line: usize, snippet: &str) -> Result<T>
+    where
+        Self: Sized,
+    {
+        self.at(Location::line(line, snippet))
+    }
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn at(self, location: Location) -> Result<T> {
+        self.map_err(|e| Error::Located(location, Box::new(e)))
+    }
 }
 
-impl ::std::error::Error for Error {}
+// Parses every line of the input, an error is located at its line
+pub fn parse_lines<T, F>(input: &str, mut parse: F) -> Result<Vec<T>>
+where
+    F: FnMut(&str) -> Result<T>,
+{
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parse(line).at_line(i + 1, line))
+        .collect()
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) | Error::Config(_) => 2,
+            Error::DayDoesNotExist(_) | Error::MissingPart(_) => 3,
+            Error::MissingSessionToken | Error::InvalidSessionToken(_) => 4,
+            Error::Network(_)
+            | Error::ServerError(_)
+            | Error::UnexpectedStatus(_)
+            | Error::UnexpectedResponse => 5,
+            Error::InputNotCached(_) | Error::Offline(_) => 6,
+            Error::Input(_)
+            | Error::ParseInt(_)
+            | Error::MissingExample(_, _)
+            | Error::PartsFailed(_)
+            | Error::Timeout(_)
+            | Error::StillRunning(_)
+            | Error::Panicked(_) => 7,
+            Error::VerificationFailed(_) => 8,
+            Error::Bincode(_)
+            | Error::Io(_)
+            | Error::Json(_)
+            | Error::Cache(_)
+            | Error::InvalidFile(_, _) => 9,
+            Error::Fmt(_) => 10,
+            Error::DayNotUnlocked(_) => 11,
+            Error::AnswerRejected(_) => 12,
+            Error::Located(_, e) | Error::Part(_, _, e) => e.exit_code(),
+        }
+    }
+
+    // Formats the error together with every error in its cause chain
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = ::std::error::Error::source(self);
+        while let Some(e) = source {
+            report.push_str(&format!("\n  caused by: {}", e));
+            source = e.source();
+        }
+        report
+    }
+}
+
+impl ::std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        match self {
+            Error::Network(e) => Some(e),
+            Error::Bincode(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Fmt(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::ParseInt(e) => Some(e),
+            Error::Located(_, e) | Error::Part(_, _, e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
 
-impl ::std::fmt::Display for Error {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        <Self as ::std::fmt::Debug>::fmt(self, f)
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Network(_) => write!(f, "network request failed"),
+            Error::Bincode(_) => write!(f, "could not read legacy cache"),
+            Error::Io(_) => write!(f, "I/O error"),
+            Error::Fmt(_) => write!(f, "formatting failed"),
+            Error::Json(_) => write!(f, "invalid JSON"),
+            Error::ParseInt(_) => write!(f, "invalid integer"),
+            Error::Usage(message) => write!(f, "{}", message),
+            Error::DayDoesNotExist(day) => write!(f, "{} does not exist", day),
+            Error::MissingPart(part) => write!(f, "day has no {}", part),
+            Error::InputNotCached(day) => write!(f, "input for {} is not cached", day),
+            Error::Cache(message) => write!(f, "invalid cache: {}", message),
+            Error::Config(message) => write!(f, "invalid config file: {}", message),
+            Error::InvalidFile(file, message) => write!(f, "invalid {}: {}", file, message),
+            Error::VerificationFailed(count) => {
+                write!(f, "{} answer(s) do not match the expected answer", count)
+            }
+            Error::PartsFailed(count) => write!(f, "{} part(s) failed", count),
+            Error::MissingSessionToken => write!(
+                f,
+                "no session token, pass --session, set {} or create token.txt",
+                crate::config::SESSION_ENV
+            ),
+            Error::InvalidSessionToken(status) => {
+                write!(f, "session token was rejected ({})", status)
+            }
+            Error::DayNotUnlocked(url) => write!(f, "puzzle is not unlocked yet ({})", url),
+            Error::ServerError(status) => write!(f, "server keeps failing ({})", status),
+            Error::UnexpectedStatus(status) => write!(f, "unexpected response ({})", status),
+            Error::Offline(url) => write!(f, "cannot access {} while offline", url),
+            Error::Input(message) => write!(f, "invalid input: {}", message),
+            Error::MissingExample(day, what) => {
+                write!(f, "{} is not on the puzzle page of {}", what, day)
+            }
+            Error::AnswerRejected(reason) => write!(f, "answer not accepted: {}", reason),
+            Error::UnexpectedResponse => write!(f, "could not understand the server's response"),
+            Error::Timeout(duration) => write!(
+                f,
+                "no answer within {}",
+                crate::framework::format_duration(*duration)
+            ),
+            Error::StillRunning(count) => {
+                write!(f, "{} part(s) that timed out are still running", count)
+            }
+            Error::Panicked(message) => write!(f, "panicked: {}", message),
+            Error::Located(location, _) => {
+                write!(f, "at line {}", location.line)?;
+                if let Some(column) = location.column {
+                    write!(f, ", column {}", column)?;
+                }
+                write!(f, "\n    {}", location.snippet)?;
+                if let Some(column) = location.column {
+                    write!(f, "\n    {:>width$}", "^", width = column)?;
+                }
+                Ok(())
+            }
+            Error::Part(day, part, _) => write!(f, "{} {} failed", day, part),
+        }
     }
 }
 
@@ -0,0 +1,91 @@
+// Examples and their answers, extracted from the puzzle description
+use regex::Regex;
+
+// Maps the `block`th code block of the puzzle page to the `answer`th emphasized
+// answer, both counted from 0. `part` is the name of the part it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Example {
+    pub part: &'static str,
+    pub block: usize,
+    pub answer: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PuzzlePage {
+    pub blocks: Vec<String>,  // every <pre><code> block
+    pub answers: Vec<String>, // every emphasized code outside of the blocks, in order
+}
+
+// The description of a day lives next to its input
+pub fn page_url(input_url: &str) -> String {
+    input_url.trim_end_matches("/input").to_owned()
+}
+
+// Pages fetched before part 1 was solved only describe part 1
+pub fn has_part_two(html: &str) -> bool {
+    html.contains("--- Part Two ---")
+}
+
+fn strip_tags(html: &str) -> String {
+    lazy_static! {
+        static ref TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+    }
+    TAG.replace_all(html, "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+impl PuzzlePage {
+    pub fn parse(html: &str) -> PuzzlePage {
+        lazy_static! {
+            static ref BLOCK: Regex = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+            // Answers are written as <code><em>17</em></code>, or at the end of a
+            // calculation like <code>37 * 31 = <em>1147</em></code>
+            static ref ANSWER: Regex =
+                Regex::new(r"<code>[^<]*<em>([^<]*)</em></code>|<em><code>([^<]*)</code></em>")
+                    .unwrap();
+        }
+        let prose = BLOCK.replace_all(html, "");
+        PuzzlePage {
+            blocks: BLOCK
+                .captures_iter(html)
+                .map(|c| strip_tags(&c[1]))
+                .collect(),
+            answers: ANSWER
+                .captures_iter(&prose)
+                .filter_map(|c| c.get(1).or_else(|| c.get(2)))
+                .map(|answer| strip_tags(answer.as_str()).trim().to_owned())
+                .collect(),
+        }
+    }
+}
+
+#[test]
+fn examples_test() {
+    let html = r#"<article class="day-desc"><h2>--- Day 1 ---</h2>
+<p>For example:</p>
+<pre><code>#1 @ 1,3: 4x4
+#2 @ <em>3,1</em>: 4x4
+</code></pre>
+<p>The overlap is <code><em>4</em></code> square inches, not <em>5</em>.</p>
+<pre><code>a &lt;-&gt; b &amp; c</code></pre>
+<p>The result is <code><em>&quot;ab&quot;</em></code>, or <em><code>ba</code></em>.</p>
+<p>That makes <code>37 * 31 = <em>1147</em></code>.</p>
+</article>"#;
+    let page = PuzzlePage::parse(html);
+    assert_eq!(page.blocks, vec!["#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n", "a <-> b & c"]);
+    assert_eq!(page.answers, vec!["4", "\"ab\"", "ba", "1147"]);
+    assert_eq!(PuzzlePage::parse("<p>nothing</p>"), PuzzlePage::default());
+    assert!(!has_part_two(html));
+    assert!(has_part_two(r#"<h2 id="part2">--- Part Two ---</h2>"#));
+
+    assert_eq!(
+        page_url("https://adventofcode.com/2018/day/3/input"),
+        "https://adventofcode.com/2018/day/3"
+    );
+}
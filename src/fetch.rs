@@ -0,0 +1,178 @@
+// Rate limited and retrying HTTP access to the puzzle server
+use crate::{Error, Result};
+use reqwest::header::{COOKIE, USER_AGENT};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "advent_of_code_2018/",
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("CARGO_PKG_AUTHORS"),
+    ")"
+);
+
+#[derive(Debug, Clone)]
+pub struct Fetcher {
+    client: Client,
+    user_agent: String,
+    min_interval: Duration,
+    max_retries: u32,
+    initial_backoff: Duration,
+    offline: bool,
+    last_request: Option<Instant>,
+}
+
+impl Fetcher {
+    pub fn new(client: Client) -> Fetcher {
+        Fetcher {
+            client,
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            min_interval: Duration::from_secs(5),
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            offline: false,
+            last_request: None,
+        }
+    }
+
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Fetcher {
+        self.min_interval = min_interval;
+        self
+    }
+
+    pub fn with_retries(mut self, max_retries: u32, initial_backoff: Duration) -> Fetcher {
+        self.max_retries = max_retries;
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn with_offline(mut self, offline: bool) -> Fetcher {
+        self.offline = offline;
+        self
+    }
+
+    fn wait_for_rate_limit(&mut self) {
+        if let Some(last_request) = self.last_request {
+            let next_request = last_request + self.min_interval;
+            let now = Instant::now();
+            if now < next_request {
+                thread::sleep(next_request - now);
+            }
+        }
+        self.last_request = Some(Instant::now());
+    }
+
+    // Sends the request built by `build`, retrying with exponential backoff
+    // while the server responds with a 5xx status.
+    fn send<F>(&mut self, url: &str, token: Option<&str>, build: F) -> Result<String>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        if self.offline {
+            return Err(Error::Offline(url.to_owned()));
+        }
+        let token = token.ok_or(Error::MissingSessionToken)?;
+
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            self.wait_for_rate_limit();
+            let mut response = build(&self.client)
+                .header(USER_AGENT, self.user_agent.as_str())
+                .header(COOKIE, format!("session={}", token))
+                .send()?;
+
+            let status = response.status();
+            match status {
+                StatusCode::OK => return Ok(response.text()?),
+                StatusCode::NOT_FOUND => return Err(Error::DayNotUnlocked(url.to_owned())),
+                StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED => {
+                    return Err(Error::InvalidSessionToken(status));
+                }
+                _ if status.is_server_error() && attempt < self.max_retries => {
+                    attempt += 1;
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                _ if status.is_server_error() => return Err(Error::ServerError(status)),
+                _ => return Err(Error::UnexpectedStatus(status)),
+            }
+        }
+    }
+
+    pub fn get(&mut self, url: &str, token: Option<&str>) -> Result<String> {
+        self.send(url, token, |client| client.get(url))
+    }
+
+    pub fn post(
+        &mut self,
+        url: &str,
+        token: Option<&str>,
+        form: &[(&str, &str)],
+    ) -> Result<String> {
+        self.send(url, token, |client| client.post(url).form(form))
+    }
+}
+
+#[test]
+fn fetch_test() {
+    use crate::test_server::TestServer;
+
+    let fast = |fetcher: Fetcher| {
+        fetcher
+            .with_min_interval(Duration::from_millis(0))
+            .with_retries(2, Duration::from_millis(1))
+    };
+
+    // Retries server errors and sends the session and user agent
+    let server = TestServer::start(vec![(503, ""), (500, ""), (200, "puzzle input")]);
+    let mut fetcher = fast(Fetcher::new(Client::new()));
+    let url = format!("{}/2018/day/1/input", server.url);
+    assert_eq!(fetcher.get(&url, Some("abc")).unwrap(), "puzzle input");
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[2].starts_with("GET /2018/day/1/input "));
+    assert!(requests[2].to_lowercase().contains("cookie: session=abc"));
+    assert!(requests[2]
+        .to_lowercase()
+        .contains(&format!("user-agent: {}", DEFAULT_USER_AGENT.to_lowercase())));
+
+    // Gives up once the retries are exhausted
+    let server = TestServer::start(vec![(502, ""), (502, ""), (502, "")]);
+    let mut fetcher = fast(Fetcher::new(Client::new()));
+    match fetcher.get(&server.url, Some("abc")) {
+        Err(Error::ServerError(status)) => assert_eq!(status, StatusCode::BAD_GATEWAY),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // Distinguishes locked days from bad sessions
+    let server = TestServer::start(vec![(404, ""), (400, ""), (401, "")]);
+    let mut fetcher = fast(Fetcher::new(Client::new()));
+    match fetcher.get(&server.url, Some("abc")) {
+        Err(Error::DayNotUnlocked(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    for _ in 0..2 {
+        match fetcher.get(&server.url, Some("abc")) {
+            Err(Error::InvalidSessionToken(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    // Rate limits consecutive requests
+    let server = TestServer::start(vec![(200, "a"), (200, "b")]);
+    let mut fetcher = Fetcher::new(Client::new()).with_min_interval(Duration::from_millis(200));
+    let start = Instant::now();
+    fetcher.get(&server.url, Some("abc")).unwrap();
+    fetcher.get(&server.url, Some("abc")).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(200));
+
+    // Fails fast when offline
+    let mut fetcher = Fetcher::new(Client::new()).with_offline(true);
+    match fetcher.get("http://127.0.0.1:1/", Some("abc")) {
+        Err(Error::Offline(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
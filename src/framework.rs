@@ -1,23 +1,54 @@
 macro_rules! day {
-    ($name:tt, $url:tt, $part1:tt, $part2:tt) => {
+    ($year:tt, $day:tt, $parse:path => $parsed:ty, $part1:tt, $part2:tt) => {
+        day!($year, $day, $parse => $parsed, $part1, $part2, examples: []);
+    };
+    (
+        $year:tt, $day:tt, $parse:path => $parsed:ty, $part1:tt, $part2:tt,
+        examples: [$($part:ident($block:expr => $answer:expr)),*$(,)*]
+    ) => {
         #[allow(unused_imports)]
         use crate::{Error, Result};
-        pub(crate) fn register_day(fw: &mut crate::framework::Framework) {
-            fw.register_day(
-                stringify!($name),
-                $url,
-                day_callback!($part1),
-                day_callback!($part2),
-            );
+        pub struct Solution;
+        impl crate::framework::Solver for Solution {
+            type Parsed = $parsed;
+            const HAS_PART1: bool = solver_part!(has $part1);
+            const HAS_PART2: bool = solver_part!(has $part2);
+            const EXAMPLES: &'static [crate::examples::Example] = &[$(
+                crate::examples::Example {
+                    part: stringify!($part),
+                    block: $block,
+                    answer: $answer,
+                }
+            ),*];
+
+            fn parse(input: &str) -> Result<$parsed> {
+                $parse(input)
+            }
+            fn part1(parsed: &$parsed) -> Result<crate::answer::Answer> {
+                solver_part!(call $part1, parsed, "part1")
+            }
+            fn part2(parsed: &$parsed) -> Result<crate::answer::Answer> {
+                solver_part!(call $part2, parsed, "part2")
+            }
+        }
+        pub fn register_day(fw: &mut crate::framework::Framework) {
+            fw.register_day::<Solution>(crate::framework::DayId::new($year, $day));
         }
     };
 }
-macro_rules! day_callback {
-    (!) => {
-        None
+macro_rules! solver_part {
+    (has !) => {
+        false
+    };
+    (has $callback:ident) => {
+        true
     };
-    ($callback:ident) => {
-        Some((|input| $callback(input).map(|x| x.to_string())) as (fn(&str) -> Result<String>))
+    (call !, $parsed:ident, $name:expr) => {{
+        let _ = $parsed;
+        Err(crate::Error::MissingPart($name))
+    }};
+    (call $callback:ident, $parsed:ident, $name:expr) => {
+        $callback($parsed).map(crate::answer::Answer::from)
     };
 }
 #[allow(unused_macros)]
@@ -26,134 +57,1685 @@ macro_rules! assert_results {
     ($fn:ident, $($input:tt => $expected:expr),+$(,)*) => {
         $(
             assert_eq!(
-                $fn($input).expect("function should run without error"),
+                $fn(&<Solution as crate::framework::Solver>::parse($input)
+                    .expect("input should parse"))
+                    .expect("function should run without error"),
                 $expected
             );
         )+;
     };
 }
 
+use crate::answer::Answer;
+use crate::cache::{self, InputCache};
+use crate::config::{self, Config};
+use crate::examples::{self, Example, PuzzlePage};
+use crate::fetch::Fetcher;
+use crate::memory::{self, Usage};
+use crate::submit::{self, History, Verdict};
+use crate::watch::{self, Watcher};
 use crate::{Error, Result};
-use reqwest::{Client, StatusCode};
+use reqwest::Client;
 use colored::*;
+use serde_json::{json, Value};
+use std::any::Any;
 use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 
+pub const DEFAULT_PROFILE: &str = "default";
+pub const ANSWERS_FILE: &str = "answers.json";
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
 #[derive(Clone)]
 pub struct Framework {
-    days: BTreeMap<&'static str, Day>,
+    days: BTreeMap<DayId, Day>,
     token: Option<String>,
-    input_cache: HashMap<String, String>,
-    no_fetch_before: Option<Instant>,
+    profiles: BTreeMap<String, String>,
+    profile: Option<String>, // None for the default session token
+    base_url: String,
+    cache: InputCache,
+    answers: HashMap<String, BTreeMap<String, Answer>>,
+    fetcher: Fetcher,
+    format: OutputFormat,
+    timeout: Option<Duration>, // per part, None to wait for every part
+    timed_out: Arc<AtomicUsize>, // parts that timed out and are still running
+}
+
+// Identifies a puzzle by its event and day, so several events can be hosted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DayId {
+    pub year: u16,
+    pub day: u8,
+}
+
+impl DayId {
+    pub fn new(year: u16, day: u8) -> DayId {
+        DayId { year, day }
+    }
+
+    pub fn name(self) -> String {
+        format!("day{:02}", self.day)
+    }
+
+    pub fn input_url(self) -> String {
+        format!("{}/{}/day/{}/input", config::DEFAULT_BASE_URL, self.year, self.day)
+    }
+}
+
+impl fmt::Display for DayId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&format!("{}/day{:02}", self.year, self.day))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json, // one JSON object per line and part
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parts {
+    Both,
+    Part1,
+    Part2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    File(PathBuf),
+    Stdin,
+    Cache,  // cached input only, never touches the network
+    Remote, // cached input, downloaded when it is not cached yet
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail,
+    New,
+    Timeout,
+    Panic,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Pass => "PASS",
+            Status::Fail => "FAIL",
+            Status::New => "NEW",
+            Status::Timeout => "TIMEOUT",
+            Status::Panic => "PANIC",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PartResult {
+    pub day: DayId,
+    pub part: &'static str,
+    pub answer: Option<Answer>, // None when the part timed out or panicked
+    pub duration: Duration,
+    pub parse_duration: Duration, // shared by the parts of a day
+    pub status: Option<Status>, // None when not run against the account's own input
+    pub memory: Option<Usage>,  // None unless allocations are counted
+}
+
+// A part solved for one of several inputs, `result` holds the error report when
+// the part failed on that input
+#[derive(Debug, Clone)]
+pub struct MatrixCell {
+    pub input: String,
+    pub day: DayId,
+    pub part: &'static str,
+    pub result: ::std::result::Result<PartResult, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub day: DayId,
+    pub part: &'static str,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    pub iterations: usize,
+}
+
+// A day's solution, the input is parsed once and then shared by both parts.
+// `day!` implements this for every day.
+pub trait Solver {
+    type Parsed: Send + Sync + 'static;
+    const HAS_PART1: bool = true;
+    const HAS_PART2: bool = true;
+    const EXAMPLES: &'static [Example] = &[];
+
+    fn parse(input: &str) -> Result<Self::Parsed>;
+    fn part1(parsed: &Self::Parsed) -> Result<Answer>;
+    fn part2(parsed: &Self::Parsed) -> Result<Answer>;
+}
+
+type AnyParsed = dyn Any + Send + Sync;
+type PartFn = fn(&AnyParsed) -> Result<Answer>;
+
+fn parse_any<S: Solver>(input: &str) -> Result<Arc<AnyParsed>> {
+    Ok(Arc::new(S::parse(input)?))
+}
+
+fn downcast<S: Solver>(parsed: &AnyParsed) -> &S::Parsed {
+    parsed
+        .downcast_ref()
+        .expect("parsed input belongs to another day")
+}
+
+fn part1_any<S: Solver>(parsed: &AnyParsed) -> Result<Answer> {
+    S::part1(downcast::<S>(parsed))
+}
+
+fn part2_any<S: Solver>(parsed: &AnyParsed) -> Result<Answer> {
+    S::part2(downcast::<S>(parsed))
+}
+
+// How a part that ran on its own thread ended
+enum Outcome {
+    Finished(Result<Answer>),
+    Unfinished(Status, Error), // timed out or panicked
+}
+
+// What running a part took
+#[derive(Debug, Clone, Copy)]
+struct Measurement {
+    duration: Duration,
+    memory: Option<Usage>,
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_duration(self.duration))?;
+        if let Some(memory) = self.memory {
+            write!(f, ", {}", memory)?;
+        }
+        Ok(())
+    }
+}
+
+impl Outcome {
+    fn into_result(self) -> Result<Answer> {
+        match self {
+            Outcome::Finished(answer) => answer,
+            Outcome::Unfinished(_, e) => Err(e),
+        }
+    }
 }
 
 #[derive(Clone)]
 struct Day {
-    name: &'static str,
-    url: &'static str,
-    part1: Option<fn(&str) -> Result<String>>,
-    part2: Option<fn(&str) -> Result<String>>,
+    id: DayId,
+    url: String,
+    parse: fn(&str) -> Result<Arc<AnyParsed>>,
+    part1: Option<PartFn>,
+    part2: Option<PartFn>,
+    examples: &'static [Example],
 }
 
 impl Framework {
-    pub fn new() -> Framework {
-        use std::fs::{read, read_to_string};
-        let token = read_to_string("token.txt").ok();
-        let input_cache = read("cache.dat")
-            .ok()
-            .and_then(|x| ::bincode::deserialize::<HashMap<String, String>>(&x[..]).ok())
-            .unwrap_or(HashMap::new());
-        Framework {
+    pub fn new() -> Result<Framework> {
+        let config = Config::load()?;
+        let cache = InputCache::open("cache")?;
+        let answers = load_answers()?;
+        Ok(Framework {
             days: BTreeMap::new(),
-            token,
-            input_cache,
-            no_fetch_before: None,
+            base_url: config.base_url().to_owned(),
+            token: config.session,
+            profiles: config.profiles,
+            profile: None,
+            cache,
+            answers,
+            fetcher: Fetcher::new(Client::new()),
+            format: OutputFormat::Text,
+            timeout: Some(Duration::from_secs(DEFAULT_TIMEOUT_SECS)),
+            timed_out: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn set_session_token(&mut self, token: String) {
+        self.token = Some(token);
+    }
+
+    pub fn set_base_url(&mut self, base_url: String) {
+        self.base_url = base_url;
+    }
+
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names = vec![DEFAULT_PROFILE.to_owned()];
+        names.extend(self.profiles.keys().filter(|&name| name != DEFAULT_PROFILE).cloned());
+        names
+    }
+
+    // Selects whose session token and inputs are used
+    pub fn set_profile(&mut self, profile: &str) -> Result<()> {
+        self.profile = if profile == DEFAULT_PROFILE {
+            None
+        } else if self.profiles.contains_key(profile) {
+            Some(profile.to_owned())
+        } else {
+            return Err(Error::Usage(format!("unknown profile {}", profile)));
+        };
+        Ok(())
+    }
+
+    pub fn profile_name(&self) -> &str {
+        self.profile.as_ref().map_or(DEFAULT_PROFILE, String::as_str)
+    }
+
+    fn session_token(&self) -> Option<&str> {
+        match self.profile {
+            Some(ref profile) => self.profiles.get(profile).map(String::as_str),
+            None => self.token.as_ref().map(String::as_str),
         }
     }
 
-    pub fn register_day(
-        &mut self,
-        name: &'static str,
-        url: &'static str,
-        part1: Option<fn(&str) -> Result<String>>,
-        part2: Option<fn(&str) -> Result<String>>,
-    ) -> bool {
-        if self.days.contains_key(&name) {
+    // Inputs and answers of other profiles are kept apart from the default ones
+    fn input_key(&self, day: &Day) -> String {
+        match self.profile {
+            Some(ref profile) => format!("{}#{}", day.url, profile),
+            None => day.url.clone(),
+        }
+    }
+
+    pub fn set_fetcher(&mut self, fetcher: Fetcher) {
+        self.fetcher = fetcher;
+    }
+
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.format = format;
+    }
+
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn register_day<S: Solver>(&mut self, id: DayId) -> bool {
+        if self.days.contains_key(&id) {
             return false;
         }
 
         let day = Day {
-            name,
-            url,
-            part1,
-            part2,
+            id,
+            url: id.input_url(),
+            parse: parse_any::<S>,
+            part1: if S::HAS_PART1 { Some(part1_any::<S>) } else { None },
+            part2: if S::HAS_PART2 { Some(part2_any::<S>) } else { None },
+            examples: S::EXAMPLES,
         };
-        self.days.insert(name, day);
+        self.days.insert(id, day);
 
         true
     }
 
-    fn cache_input(&mut self, client: &Client, url: &'static str) -> Result<()> {
-        if let Some(no_fetch_before) = self.no_fetch_before {
-            let now = Instant::now();
-            if now < no_fetch_before {
-                std::thread::sleep(no_fetch_before - now);
+    pub fn has_day(&self, id: DayId) -> bool {
+        self.days.contains_key(&id)
+    }
+
+    pub fn day_ids<'a>(&'a self) -> impl Iterator<Item = DayId> + 'a {
+        self.days.keys().cloned()
+    }
+
+    // The most recent event, which days without a year refer to
+    pub fn latest_year(&self) -> Option<u16> {
+        self.days.keys().map(|id| id.year).max()
+    }
+
+    pub fn list(&self) {
+        for day in self.days.values() {
+            let mut parts = Vec::new();
+            if day.part1.is_some() {
+                parts.push("part1");
+            }
+            if day.part2.is_some() {
+                parts.push("part2");
             }
+            let cached = if self.cache.contains(&self.input_key(&day)) {
+                "cached".bright_green()
+            } else {
+                "not cached".yellow()
+            };
+            println!(
+                "{} {} ({})",
+                day.id.to_string().bright_cyan().bold(),
+                parts.join(" "),
+                cached
+            );
         }
+    }
 
-        if self.input_cache.get(url).is_some() {
-            return Ok(());
+    pub fn list_cache(&self) -> Result<()> {
+        for (url, entry) in self.cache.entries() {
+            let state = if self.cache.is_modified(url)? {
+                "modified".yellow()
+            } else {
+                "ok".bright_green()
+            };
+            println!(
+                "{} {} {} {} ({})",
+                entry.file.bright_cyan().bold(),
+                cache::format_timestamp(entry.fetched_at),
+                &entry.hash[..16],
+                url,
+                state
+            );
         }
-        let token = self.token.as_ref().ok_or(Error::MissingSessionToken)?;
+        Ok(())
+    }
 
-        let mut response = client
-            .get(url)
-            .header("cookie", format!("session={}", token))
-            .send()?;
+    pub fn show_cache(&self, day: DayId) -> Result<()> {
+        let day = self.get_day(day)?;
+        let content = self
+            .cache
+            .get(&self.input_key(&day))?
+            .ok_or_else(|| Error::InputNotCached(day.id.to_string()))?;
+        println!("{}", content);
+        Ok(())
+    }
 
-        if response.status() != StatusCode::OK {
-            return Err(Error::InvalidSessionToken(response.status()));
+    pub fn invalidate_cache(&mut self, day: DayId) -> Result<()> {
+        let day = self.get_day(day)?;
+        if self.cache.invalidate(&self.input_key(&day))? {
+            println!("{} {}", day.id.to_string().bright_cyan().bold(), "invalidated");
+        } else {
+            println!("{} {}", day.id.to_string().bright_cyan().bold(), "was not cached".yellow());
         }
+        Ok(())
+    }
 
-        let mut result = response.text()?;
+    pub fn import_cache(&mut self, day: DayId, path: &PathBuf) -> Result<()> {
+        let day = self.get_day(day)?;
+        let content = normalize_input(::std::fs::read_to_string(path)?);
+        self.cache.insert(&self.input_key(&day), &content)?;
+        println!("{} {}", day.id.to_string().bright_cyan().bold(), "imported");
+        Ok(())
+    }
 
-        // Strip trailing newline characters
-        while let Some(last) = result.pop() {
-            if last == '\r' || last == '\n' {
-                continue;
-            }
-            result.push(last);
-            break;
+    pub fn clear_cache(&mut self) -> Result<()> {
+        self.cache.clear()
+    }
+
+    fn save_answers(&self) -> Result<()> {
+        let answers = self
+            .answers
+            .iter()
+            .map(|(url, parts)| {
+                let parts = parts
+                    .iter()
+                    .map(|(part, answer)| (part.clone(), answer.to_json()))
+                    .collect::<::serde_json::Map<_, _>>();
+                (url.clone(), Value::Object(parts))
+            })
+            .collect::<::serde_json::Map<_, _>>();
+        let serialized = ::serde_json::to_string_pretty(&Value::Object(answers))?;
+        ::std::fs::write(ANSWERS_FILE, serialized)?;
+        Ok(())
+    }
+
+    // Parts without an answer leave the expected answer as it was
+    pub fn record_answer(&mut self, result: &PartResult) -> Result<()> {
+        let answer = match result.answer {
+            Some(ref answer) => answer.clone(),
+            None => return Ok(()),
+        };
+        let day = self.get_day(result.day)?;
+        let key = self.input_key(&day);
+        self.answers
+            .entry(key)
+            .or_insert_with(BTreeMap::new)
+            .insert(result.part.to_owned(), answer);
+        self.save_answers()
+    }
+
+    fn check_answer(&self, day: &Day, part: &str, answer: &Answer) -> Status {
+        match self.answers.get(&self.input_key(day)).and_then(|answers| answers.get(part)) {
+            Some(expected) if answer.matches(expected) => Status::Pass,
+            Some(_) => Status::Fail,
+            None => Status::New,
         }
+    }
 
-        self.input_cache.insert(url.to_owned(), result);
+    fn get_day(&self, day: DayId) -> Result<Day> {
+        self.days
+            .get(&day)
+            .cloned()
+            .ok_or_else(|| Error::DayDoesNotExist(day.to_string()))
+    }
 
-        let serialized = ::bincode::serialize(&self.input_cache)?;
-        ::std::fs::write("cache.dat", serialized)?;
+    fn cache_input(&mut self, url: &str, key: &str) -> Result<String> {
+        if let Some(input) = self.cache.get(key)? {
+            return Ok(input);
+        }
 
-        self.no_fetch_before = Some(Instant::now() + Duration::from_secs(5));
+        // Inputs stay cached under the canonical URL, whichever server they came from
+        let remote_url = config::rebase_url(url, &self.base_url);
+        let token = self.session_token().map(str::to_owned);
+        let token = token.as_ref().map(String::as_str);
+        let result = normalize_input(self.fetcher.get(&remote_url, token)?);
+        self.cache.insert(key, &result)?;
+        Ok(result)
+    }
 
+    pub fn fetch(&mut self, day: DayId) -> Result<()> {
+        let day = self.get_day(day)?;
+        let key = self.input_key(&day);
+        self.cache_input(&day.url, &key)?;
+        println!("{} {}", day.id.to_string().bright_cyan().bold(), "cached");
         Ok(())
     }
 
-    pub fn execute(&mut self, client: &Client, day: &str) -> Result<()> {
-        let day = self
-            .days
-            .get(day)
-            .ok_or_else(|| Error::DayDoesNotExist(day.to_owned()))?
-            .clone();
+    fn load_page(&mut self, day: &Day, source: &InputSource) -> Result<PuzzlePage> {
+        let url = examples::page_url(&day.url);
+        let html = match source {
+            InputSource::Cache => self
+                .cache
+                .get(&url)?
+                .ok_or_else(|| Error::InputNotCached(day.id.to_string()))?,
+            _ => {
+                // Fetched again until part 2 is unlocked, or its examples stay missing
+                if let Some(html) = self.cache.get(&url)? {
+                    if !examples::has_part_two(&html) {
+                        self.cache.invalidate(&url)?;
+                    }
+                }
+                self.cache_input(&url, &url)?
+            }
+        };
+        Ok(PuzzlePage::parse(&html))
+    }
 
-        self.cache_input(client, day.url)?;
-        let input = self.input_cache.get(day.url).unwrap();
-        if let Some(part1) = day.part1 {
-            println!("\n{} {}", day.name.bright_cyan().bold(), "part1");
-            println!("{}", part1(input)?.bright_green());
+    pub fn show_examples(&mut self, day: DayId, source: &InputSource) -> Result<()> {
+        let day = self.get_day(day)?;
+        let page = self.load_page(&day, source)?;
+        for (i, block) in page.blocks.iter().enumerate() {
+            println!("\n{} block {}", day.id.to_string().bright_cyan().bold(), i);
+            println!("{}", block.trim_end());
         }
-        if let Some(part2) = day.part2 {
-            println!("\n{} {}", day.name.bright_cyan().bold(), "part2");
-            println!("{}", part2(input)?.bright_green());
+        println!();
+        for (i, answer) in page.answers.iter().enumerate() {
+            println!(
+                "{} answer {}: {}",
+                day.id.to_string().bright_cyan().bold(),
+                i,
+                answer.bright_green()
+            );
+        }
+        Ok(())
+    }
+
+    // The input of a day as given by the source, for commands that work on it
+    // outside of the solvers
+    pub fn read_input(&mut self, day: DayId, source: &InputSource) -> Result<String> {
+        let day = self.get_day(day)?;
+        self.load_input(&day, source)
+    }
+
+    // Solves the examples a day declared and checks them against the answers
+    // on its puzzle page. Days without examples have nothing to report.
+    pub fn run_examples(
+        &mut self,
+        day: DayId,
+        parts: Parts,
+        source: &InputSource,
+    ) -> Result<Vec<PartResult>> {
+        let day = self.get_day(day)?;
+        if day.examples.is_empty() {
+            return Ok(Vec::new());
         }
+        let page = self.load_page(&day, source)?;
 
+        let mut results = Vec::new();
+        for (part_name, part) in Self::selected_parts(&day, parts) {
+            for example in day.examples.iter().filter(|e| e.part == part_name) {
+                let input = page.blocks.get(example.block).ok_or_else(|| {
+                    Error::MissingExample(day.id.to_string(), format!("block {}", example.block))
+                })?;
+                let expected = page.answers.get(example.answer).ok_or_else(|| {
+                    Error::MissingExample(day.id.to_string(), format!("answer {}", example.answer))
+                })?;
+                let expected = Answer::from(expected.as_str());
+                if self.format == OutputFormat::Text {
+                    println!(
+                        "\n{} {} block {}",
+                        day.id.to_string().bright_cyan().bold(),
+                        part_name,
+                        example.block
+                    );
+                }
+
+                let (parsed, parse_duration) =
+                    self.parse_input(&day, &normalize_input(input.clone()))?;
+                let (outcome, measurement) =
+                    time_part(part, &parsed, self.timeout, &self.timed_out);
+                let answer = match outcome {
+                    Outcome::Finished(Ok(answer)) => answer,
+                    Outcome::Finished(Err(e)) => {
+                        return Err(self.report_error(&day, part_name, e, measurement.duration));
+                    }
+                    Outcome::Unfinished(status, e) => {
+                        results.push(self.report_unfinished(
+                            &day,
+                            part_name,
+                            status,
+                            e,
+                            measurement,
+                            parse_duration,
+                        ));
+                        continue;
+                    }
+                };
+                let status = if answer.matches(&expected) {
+                    Status::Pass
+                } else {
+                    Status::Fail
+                };
+                match self.format {
+                    OutputFormat::Text => {
+                        print_answer(&answer, measurement, Some(status), Some(&expected))
+                    }
+                    OutputFormat::Json => print_json(json!({
+                        "year": day.id.year,
+                        "day": day.id.name(),
+                        "input": self.profile_name(),
+                        "part": part_name,
+                        "block": example.block,
+                        "answer": answer.to_json(),
+                        "kind": answer.kind(),
+                        "duration": duration_secs(measurement.duration),
+                        "parse_duration": duration_secs(parse_duration),
+                        "memory": memory_json(measurement.memory),
+                        "status": status.as_str(),
+                        "expected": expected.to_json(),
+                        "error": Value::Null,
+                    })),
+                }
+                results.push(PartResult {
+                    day: day.id,
+                    part: part_name,
+                    answer: Some(answer),
+                    duration: measurement.duration,
+                    parse_duration,
+                    status: Some(status),
+                    memory: measurement.memory,
+                });
+            }
+        }
+        Ok(results)
+    }
+
+    fn load_input(&mut self, day: &Day, source: &InputSource) -> Result<String> {
+        match source {
+            InputSource::File(path) => Ok(normalize_input(::std::fs::read_to_string(path)?)),
+            InputSource::Stdin => {
+                let mut input = String::new();
+                ::std::io::stdin().read_to_string(&mut input)?;
+                Ok(normalize_input(input))
+            }
+            InputSource::Cache => self
+                .cache
+                .get(&self.input_key(day))?
+                .ok_or_else(|| Error::InputNotCached(day.id.to_string())),
+            InputSource::Remote => {
+                let key = self.input_key(day);
+                self.cache_input(&day.url, &key)
+            }
+        }
+    }
+
+    fn selected_parts(day: &Day, parts: Parts) -> Vec<(&'static str, PartFn)> {
+        let mut selected = Vec::new();
+        match (day.part1, parts) {
+            (Some(part1), Parts::Both) | (Some(part1), Parts::Part1) => {
+                selected.push(("part1", part1))
+            }
+            _ => {}
+        }
+        match (day.part2, parts) {
+            (Some(part2), Parts::Both) | (Some(part2), Parts::Part2) => {
+                selected.push(("part2", part2))
+            }
+            _ => {}
+        }
+        selected
+    }
+
+    fn load_input_reported(
+        &mut self,
+        day: &Day,
+        source: &InputSource,
+    ) -> Result<String> {
+        self.load_input(day, source).map_err(|e| {
+            if self.format == OutputFormat::Json {
+                print_json(json!({
+                    "year": day.id.year,
+                    "day": day.id.name(),
+                    "input": self.profile_name(),
+                    "part": Value::Null,
+                    "answer": Value::Null,
+                    "kind": Value::Null,
+                    "duration": Value::Null,
+                    "parse_duration": Value::Null,
+                    "memory": Value::Null,
+                    "status": Value::Null,
+                    "expected": Value::Null,
+                    "error": e.report(),
+                }));
+            }
+            e
+        })
+    }
+
+    fn report_error(&self, day: &Day, part_name: &'static str, e: Error, duration: Duration) -> Error {
+        let e = Error::Part(day.id.to_string(), part_name, Box::new(e));
+        if self.format == OutputFormat::Json {
+            print_json(json!({
+                "year": day.id.year,
+                "day": day.id.name(),
+                "input": self.profile_name(),
+                "part": part_name,
+                "answer": Value::Null,
+                "kind": Value::Null,
+                "duration": duration_secs(duration),
+                "parse_duration": Value::Null,
+                "memory": Value::Null,
+                "status": Value::Null,
+                "expected": Value::Null,
+                "error": e.report(),
+            }));
+        }
+        e
+    }
+
+    fn parse_input(&self, day: &Day, input: &str) -> Result<(Arc<AnyParsed>, Duration)> {
+        let start = Instant::now();
+        let parsed = panic::catch_unwind(AssertUnwindSafe(|| (day.parse)(input)))
+            .unwrap_or_else(|payload| Err(Error::Panicked(panic_message(&*payload))));
+        let duration = start.elapsed();
+        match parsed {
+            Ok(parsed) => Ok((parsed, duration)),
+            Err(e) => Err(self.report_error(day, "parse", e, duration)),
+        }
+    }
+
+    // For callers that need an answer, a part that timed out or panicked fails
+    fn run_part(
+        &self,
+        day: &Day,
+        part_name: &'static str,
+        part: PartFn,
+        parsed: &Arc<AnyParsed>,
+    ) -> Result<(Answer, Duration)> {
+        let (outcome, measurement) = time_part(part, parsed, self.timeout, &self.timed_out);
+        match outcome.into_result() {
+            Ok(answer) => Ok((answer, measurement.duration)),
+            Err(e) => Err(self.report_error(day, part_name, e, measurement.duration)),
+        }
+    }
+
+    fn report_answer(
+        &self,
+        day: &Day,
+        part_name: &'static str,
+        answer: Answer,
+        measurement: Measurement,
+        parse_duration: Duration,
+        source: &InputSource,
+    ) -> PartResult {
+        let status = match source {
+            InputSource::Cache | InputSource::Remote => {
+                Some(self.check_answer(day, part_name, &answer))
+            }
+            InputSource::File(_) | InputSource::Stdin => None,
+        };
+        let expected = self
+            .answers
+            .get(&self.input_key(day))
+            .and_then(|answers| answers.get(part_name));
+
+        match self.format {
+            OutputFormat::Text => print_answer(&answer, measurement, status, expected),
+            OutputFormat::Json => print_json(json!({
+                "year": day.id.year,
+                "day": day.id.name(),
+                "input": self.profile_name(),
+                "part": part_name,
+                "answer": answer.to_json(),
+                "kind": answer.kind(),
+                "duration": duration_secs(measurement.duration),
+                "parse_duration": duration_secs(parse_duration),
+                "memory": memory_json(measurement.memory),
+                "status": status.map(Status::as_str),
+                "expected": match status {
+                    Some(Status::Fail) => expected.map(Answer::to_json),
+                    _ => None,
+                },
+                "error": Value::Null,
+            })),
+        }
+
+        PartResult {
+            day: day.id,
+            part: part_name,
+            answer: Some(answer),
+            duration: measurement.duration,
+            parse_duration,
+            status,
+            memory: measurement.memory,
+        }
+    }
+
+    // A part that timed out or panicked is reported like an answer, so the
+    // remaining parts and days still run
+    fn report_unfinished(
+        &self,
+        day: &Day,
+        part_name: &'static str,
+        status: Status,
+        e: Error,
+        measurement: Measurement,
+        parse_duration: Duration,
+    ) -> PartResult {
+        match self.format {
+            OutputFormat::Text => println!(
+                "{} {} {}",
+                status.as_str().bright_red().bold(),
+                format!("({})", measurement).dimmed(),
+                e.to_string().bright_red()
+            ),
+            OutputFormat::Json => print_json(json!({
+                "year": day.id.year,
+                "day": day.id.name(),
+                "input": self.profile_name(),
+                "part": part_name,
+                "answer": Value::Null,
+                "kind": Value::Null,
+                "duration": duration_secs(measurement.duration),
+                "parse_duration": duration_secs(parse_duration),
+                "memory": memory_json(measurement.memory),
+                "status": status.as_str(),
+                "expected": Value::Null,
+                "error": Error::Part(day.id.to_string(), part_name, Box::new(e)).report(),
+            })),
+        }
+
+        PartResult {
+            day: day.id,
+            part: part_name,
+            answer: None,
+            duration: measurement.duration,
+            parse_duration,
+            status: Some(status),
+            memory: measurement.memory,
+        }
+    }
+
+    fn print_parse_time(&self, day: &Day, duration: Duration) {
+        if self.format == OutputFormat::Text {
+            println!(
+                "\n{} parsed {}",
+                day.id.to_string().bright_cyan().bold(),
+                format!("({})", format_duration(duration)).dimmed()
+            );
+        }
+    }
+
+    fn print_part_header(&self, day: &Day, part_name: &str) {
+        if self.format == OutputFormat::Text {
+            println!("\n{} {}", day.id.to_string().bright_cyan().bold(), part_name);
+        }
+    }
+
+    pub fn execute(
+        &mut self,
+        day: DayId,
+        parts: Parts,
+        source: &InputSource,
+    ) -> Result<Vec<PartResult>> {
+        let day = self.get_day(day)?;
+        let input = self.load_input_reported(&day, source)?;
+        let (parsed, parse_duration) = self.parse_input(&day, &input)?;
+        self.print_parse_time(&day, parse_duration);
+
+        let mut results = Vec::new();
+        for (part_name, part) in Self::selected_parts(&day, parts) {
+            self.print_part_header(&day, part_name);
+            let (outcome, measurement) = time_part(part, &parsed, self.timeout, &self.timed_out);
+            results.push(match outcome {
+                Outcome::Finished(Ok(answer)) => {
+                    self.report_answer(&day, part_name, answer, measurement, parse_duration, source)
+                }
+                Outcome::Finished(Err(e)) => {
+                    return Err(self.report_error(&day, part_name, e, measurement.duration));
+                }
+                Outcome::Unfinished(status, e) => {
+                    self.report_unfinished(&day, part_name, status, e, measurement, parse_duration)
+                }
+            });
+        }
+
+        Ok(results)
+    }
+
+    // Solves all parts of the given days on `jobs` worker threads, after every
+    // input has been loaded and parsed. Results are reported in the same order
+    // as `execute`.
+    pub fn execute_parallel(
+        &mut self,
+        days: &[DayId],
+        parts: Parts,
+        source: &InputSource,
+        jobs: Option<usize>,
+    ) -> Result<Vec<PartResult>> {
+        let mut queue = Vec::new();
+        for &day in days {
+            let day = self.get_day(day)?;
+            let input = self.load_input_reported(&day, source)?;
+            let (parsed, parse_duration) = self.parse_input(&day, &input)?;
+            for (part_name, part) in Self::selected_parts(&day, parts) {
+                queue.push((day.clone(), part_name, part, parsed.clone(), parse_duration));
+            }
+        }
+
+        let jobs = jobs.unwrap_or_else(num_cpus::get).min(queue.len()).max(1);
+        let parts = queue.iter().map(|job| (job.2, job.3.clone())).collect();
+        let finished = run_pool(parts, jobs, self.timeout, &self.timed_out);
+        let mut results = Vec::new();
+        let mut previous_day = None;
+        for (job, (outcome, measurement)) in queue.iter().zip(finished) {
+            let (ref day, part_name, _, _, parse_duration) = *job;
+            if previous_day != Some(day.id) {
+                self.print_parse_time(day, parse_duration);
+                previous_day = Some(day.id);
+            }
+            self.print_part_header(day, part_name);
+            results.push(match outcome {
+                Outcome::Finished(Ok(answer)) => {
+                    self.report_answer(day, part_name, answer, measurement, parse_duration, source)
+                }
+                Outcome::Finished(Err(e)) => {
+                    return Err(self.report_error(day, part_name, e, measurement.duration));
+                }
+                Outcome::Unfinished(status, e) => {
+                    self.report_unfinished(day, part_name, status, e, measurement, parse_duration)
+                }
+            });
+        }
+
+        Ok(results)
+    }
+
+    // Re-runs the day whenever its input or the expected answers change, and
+    // shows how the results differ from the previous run. Never returns unless
+    // the day does not exist.
+    pub fn watch(
+        &mut self,
+        day: DayId,
+        parts: Parts,
+        source: &InputSource,
+        interval: Duration,
+    ) -> Result<()> {
+        let day = self.get_day(day)?;
+        let mut watcher = Watcher::new(interval);
+        let mut previous: Option<Vec<String>> = None;
+        loop {
+            // A part that timed out would compete with the new run
+            let executed = load_answers().and_then(|answers| {
+                self.answers = answers;
+                match self.timed_out.load(Ordering::SeqCst) {
+                    0 => self.execute(day.id, parts, source),
+                    running => Err(Error::StillRunning(running)),
+                }
+            });
+            let output = match executed {
+                Ok(results) => render_results(&results),
+                // Errors are part of the output, they may be fixed by the next change
+                Err(e) => {
+                    eprintln!("{}", e.report().bright_red());
+                    e.report().lines().map(str::to_owned).collect()
+                }
+            };
+            if let Some(ref previous) = previous {
+                println!();
+                watch::print_diff(previous, &output);
+            }
+            previous = Some(output);
+
+            let mut paths = vec![PathBuf::from(ANSWERS_FILE)];
+            match source {
+                InputSource::File(path) => paths.push(path.clone()),
+                _ => paths.extend(self.cache.file_path(&self.input_key(&day))),
+            }
+            let watched = paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>();
+            println!("\n{} {}", "watching".dimmed(), watched.join(", ").dimmed());
+            watcher.set_paths(paths);
+            watcher.wait_for_change();
+        }
+    }
+
+    // Solves the days once per profile, so the answers for every input can be
+    // compared. A part failing on one input does not stop the others.
+    pub fn execute_inputs(
+        &mut self,
+        days: &[DayId],
+        parts: Parts,
+        source: &InputSource,
+        profiles: &[String],
+    ) -> Result<Vec<MatrixCell>> {
+        let previous_profile = self.profile.clone();
+        let mut cells = Vec::new();
+        let outcome = self.collect_inputs(days, parts, source, profiles, &mut cells);
+        self.profile = previous_profile;
+        outcome.map(|()| cells)
+    }
+
+    // Parses each input once per day and records a cell for every selected
+    // part; when the input fails, every part of that day gets the error.
+    fn collect_inputs(
+        &mut self,
+        days: &[DayId],
+        parts: Parts,
+        source: &InputSource,
+        profiles: &[String],
+        cells: &mut Vec<MatrixCell>,
+    ) -> Result<()> {
+        for profile in profiles {
+            self.set_profile(profile)?;
+            if self.format == OutputFormat::Text {
+                println!("\n{} {}", "input".bright_cyan().bold(), profile.bright_cyan().bold());
+            }
+            for &day in days {
+                let day = self.get_day(day)?;
+                match self.execute(day.id, parts, source) {
+                    Ok(results) => {
+                        for result in results {
+                            cells.push(MatrixCell {
+                                input: profile.clone(),
+                                day: day.id,
+                                part: result.part,
+                                result: Ok(result),
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        let report = e.report();
+                        for (part_name, _) in Self::selected_parts(&day, parts) {
+                            cells.push(MatrixCell {
+                                input: profile.clone(),
+                                day: day.id,
+                                part: part_name,
+                                result: Err(report.clone()),
+                            });
+                        }
+                    }
+                }
+            }
+        }
         Ok(())
     }
+
+    fn report_bench(&self, day: &Day, name: &'static str, mut samples: Vec<Duration>) -> BenchResult {
+        samples.sort();
+        let result = BenchResult {
+            day: day.id,
+            part: name,
+            min: samples[0],
+            median: samples[samples.len() / 2],
+            max: samples[samples.len() - 1],
+            iterations: samples.len(),
+        };
+        match self.format {
+            OutputFormat::Text => println!(
+                "{} {} min {} median {} max {} ({} iterations)",
+                day.id.to_string().bright_cyan().bold(),
+                name,
+                format_duration(result.min).bright_green(),
+                format_duration(result.median).bright_green(),
+                format_duration(result.max).bright_green(),
+                result.iterations
+            ),
+            OutputFormat::Json => print_json(json!({
+                "year": day.id.year,
+                "day": day.id.name(),
+                "input": self.profile_name(),
+                "part": name,
+                "min": duration_secs(result.min),
+                "median": duration_secs(result.median),
+                "max": duration_secs(result.max),
+                "iterations": result.iterations,
+            })),
+        }
+        result
+    }
+
+    pub fn bench(
+        &mut self,
+        day: DayId,
+        parts: Parts,
+        source: &InputSource,
+        iterations: usize,
+    ) -> Result<Vec<BenchResult>> {
+        let day = self.get_day(day)?;
+        let input = self.load_input_reported(&day, source)?;
+
+        let mut results = Vec::new();
+        let mut samples = Vec::with_capacity(iterations);
+        let mut parsed = None;
+        for _ in 0..iterations {
+            let (result, duration) = self.parse_input(&day, &input)?;
+            samples.push(duration);
+            parsed = Some(result);
+        }
+        results.push(self.report_bench(&day, "parse", samples));
+        let parsed = parsed.expect("bench runs at least one iteration");
+
+        for (part_name, part) in Self::selected_parts(&day, parts) {
+            let mut samples = Vec::with_capacity(iterations);
+            for _ in 0..iterations {
+                let (_, duration) = self.run_part(&day, part_name, part, &parsed)?;
+                samples.push(duration);
+            }
+            results.push(self.report_bench(&day, part_name, samples));
+        }
+
+        Ok(results)
+    }
+
+    // Solves one part and submits its answer. Without a specific part, submits
+    // part 1 until it is solved, then part 2.
+    pub fn submit(&mut self, day: DayId, parts: Parts, source: &InputSource) -> Result<Verdict> {
+        let day = self.get_day(day)?;
+        let history_path = Path::new(submit::HISTORY_FILE);
+        let mut history = History::read(history_path)?;
+        let key = self.input_key(&day);
+        let (parts, part_name) = match parts {
+            Parts::Part1 => (Parts::Part1, "part1"),
+            Parts::Part2 => (Parts::Part2, "part2"),
+            Parts::Both => match history.solution(&key, "part1") {
+                None => (Parts::Part1, "part1"),
+                Some(_) => (Parts::Part2, "part2"),
+            },
+        };
+        if let Some(answer) = history.solution(&key, part_name) {
+            println!(
+                "{} {} {} {}",
+                day.id.to_string().bright_cyan().bold(),
+                part_name,
+                "was already solved with".yellow(),
+                answer
+            );
+            return Ok(Verdict::AlreadySolved);
+        }
+
+        let result = match self.execute(day.id, parts, source)?.pop() {
+            Some(result) => result,
+            None => return Err(Error::Usage(format!("{} has no {}", day.id, part_name))),
+        };
+        let answer = match result.answer {
+            Some(ref answer) => answer.to_string(),
+            None => return Err(Error::PartsFailed(1)),
+        };
+        if let Some(Answer::Text(_)) = result.answer {
+            return Err(Error::Usage(format!(
+                "the answer to {} {} has to be read and submitted by hand",
+                day.id, part_name
+            )));
+        }
+        let url = format!("{}/answer", examples::page_url(&day.url));
+        let token = self.session_token().map(str::to_owned);
+        let verdict = submit::submit(
+            &mut self.fetcher,
+            &mut history,
+            &key,
+            &config::rebase_url(&url, &self.base_url),
+            token.as_ref().map(String::as_str),
+            part_name,
+            &answer,
+        );
+        history.write(history_path)?;
+
+        let verdict = verdict?;
+        let verdict_str = match verdict {
+            Verdict::Correct => verdict.as_str().bright_green().bold(),
+            _ if verdict.is_wrong() => verdict.as_str().bright_red().bold(),
+            _ => verdict.as_str().yellow().bold(),
+        };
+        println!("{} {} {}", day.id.to_string().bright_cyan().bold(), part_name, verdict_str);
+        match verdict {
+            Verdict::Correct => self.record_answer(&result)?,
+            _ if verdict.is_wrong() => {
+                let reason = format!("{} is {}", answer, verdict.as_str());
+                return Err(Error::AnswerRejected(reason));
+            }
+            _ => {}
+        }
+        Ok(verdict)
+    }
+}
+
+// The results of a run as plain lines, to compare them between runs
+fn render_results(results: &[PartResult]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for result in results {
+        let status = result.status.map(Status::as_str).unwrap_or("");
+        let answer = result.answer.as_ref().map(Answer::to_string).unwrap_or_default();
+        if answer.contains('\n') {
+            lines.push(format!("{} {}", result.part, status).trim_end().to_owned());
+            lines.extend(answer.lines().map(str::to_owned));
+        } else {
+            lines.push(format!("{} {} {}", result.part, answer, status).trim_end().to_owned());
+        }
+    }
+    lines
+}
+
+fn print_answer(
+    answer: &Answer,
+    measurement: Measurement,
+    status: Option<Status>,
+    expected: Option<&Answer>,
+) {
+    let status_str = match status {
+        Some(Status::Pass) => Status::Pass.as_str().bright_green().bold(),
+        Some(Status::New) => Status::New.as_str().yellow().bold(),
+        Some(status) => status.as_str().bright_red().bold(),
+        None => "".normal(),
+    };
+    println!(
+        "{} {} {}",
+        answer.to_string().bright_green(),
+        format!("({})", measurement).dimmed(),
+        status_str
+    );
+    if let (Some(Status::Fail), Some(expected)) = (status, expected) {
+        println!("{} {}", "expected".bright_red(), expected);
+    }
+}
+
+// Whether the result of a part is still waited for, shared by the thread that
+// runs the part and the one that gives up on it after its time budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Progress {
+    Waiting, // for a worker
+    Running(Instant),
+    Finished,
+    Abandoned,
+}
+
+type PartOutput = (thread::Result<Result<Answer>>, Measurement);
+
+fn measure_part(part: PartFn, parsed: &AnyParsed, start: Instant) -> PartOutput {
+    let (answer, memory) =
+        memory::measure(|| panic::catch_unwind(AssertUnwindSafe(|| part(parsed))));
+    let measurement = Measurement {
+        duration: start.elapsed(),
+        memory,
+    };
+    (answer, measurement)
+}
+
+// Called by the thread of a part once it is done. Returns whether its result
+// is still waited for, otherwise it no longer counts as timed out.
+fn finish_part(progress: &Mutex<Progress>, timed_out: &AtomicUsize) -> bool {
+    let mut progress = progress.lock().unwrap();
+    if *progress == Progress::Abandoned {
+        timed_out.fetch_sub(1, Ordering::SeqCst);
+        return false;
+    }
+    *progress = Progress::Finished;
+    true
+}
+
+// Gives up on a part that exceeded its time budget, and counts it as timed out
+// until it is done. Returns false when it finished in the meantime.
+fn abandon_part(progress: &Mutex<Progress>, timed_out: &AtomicUsize) -> bool {
+    let mut progress = progress.lock().unwrap();
+    if *progress == Progress::Finished {
+        return false;
+    }
+    *progress = Progress::Abandoned;
+    timed_out.fetch_add(1, Ordering::SeqCst);
+    true
+}
+
+fn part_outcome(output: PartOutput) -> (Outcome, Measurement) {
+    match output {
+        (Ok(answer), measurement) => (Outcome::Finished(answer), measurement),
+        (Err(payload), measurement) => {
+            let e = Error::Panicked(panic_message(&*payload));
+            (Outcome::Unfinished(Status::Panic, e), measurement)
+        }
+    }
+}
+
+// For a part without a result, only the time it was waited for is known
+fn unfinished_measurement(start: Instant) -> Measurement {
+    Measurement {
+        duration: start.elapsed(),
+        memory: None,
+    }
+}
+
+// Runs the part on its own thread, so a panic only loses that part and a part
+// that exceeds the time budget can be given up on. Threads cannot be stopped,
+// so a part that timed out keeps running in the background, and is counted in
+// `timed_out` until it is done.
+fn time_part(
+    part: PartFn,
+    parsed: &Arc<AnyParsed>,
+    timeout: Option<Duration>,
+    timed_out: &Arc<AtomicUsize>,
+) -> (Outcome, Measurement) {
+    let (sender, receiver) = mpsc::channel();
+    let start = Instant::now();
+    let progress = Arc::new(Mutex::new(Progress::Running(start)));
+    {
+        let parsed = parsed.clone();
+        let progress = progress.clone();
+        let timed_out = timed_out.clone();
+        thread::spawn(move || {
+            let output = measure_part(part, &*parsed, start);
+            if finish_part(&progress, &timed_out) {
+                let _ = sender.send(output);
+            }
+        });
+    }
+
+    let output = match timeout.map(|timeout| receiver.recv_timeout(timeout)) {
+        Some(Ok(output)) => Some(output),
+        Some(Err(RecvTimeoutError::Timeout)) if abandon_part(&progress, timed_out) => {
+            let e = Error::Timeout(timeout.expect("only a part with a time budget times out"));
+            return (Outcome::Unfinished(Status::Timeout, e), unfinished_measurement(start));
+        }
+        Some(Err(RecvTimeoutError::Disconnected)) => None,
+        // Finished just after the time budget, the result is on its way
+        Some(Err(RecvTimeoutError::Timeout)) | None => receiver.recv().ok(),
+    };
+    match output {
+        Some(output) => part_outcome(output),
+        None => {
+            let e = Error::Panicked("the thread of the part died".to_owned());
+            (Outcome::Unfinished(Status::Panic, e), unfinished_measurement(start))
+        }
+    }
+}
+
+// Runs the parts on `workers` threads, each of which runs one part at a time.
+// A worker whose part exceeds the time budget is given up on and replaced by a
+// new one. The outcomes are in the order of the parts.
+fn run_pool(
+    parts: Vec<(PartFn, Arc<AnyParsed>)>,
+    workers: usize,
+    timeout: Option<Duration>,
+    timed_out: &Arc<AtomicUsize>,
+) -> Vec<(Outcome, Measurement)> {
+    let count = parts.len();
+    let parts = Arc::new(parts);
+    let progress: Arc<Vec<_>> =
+        Arc::new((0..count).map(|_| Mutex::new(Progress::Waiting)).collect());
+    let next_part = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel();
+    let spawn_worker = || {
+        let parts = parts.clone();
+        let progress = progress.clone();
+        let next_part = next_part.clone();
+        let sender = sender.clone();
+        let timed_out = timed_out.clone();
+        thread::spawn(move || loop {
+            let index = next_part.fetch_add(1, Ordering::SeqCst);
+            if index >= parts.len() {
+                break;
+            }
+            let start = Instant::now();
+            *progress[index].lock().unwrap() = Progress::Running(start);
+            // Without an output, the message tells that the part started
+            if sender.send((index, None)).is_err() {
+                break;
+            }
+            let (part, ref parsed) = parts[index];
+            let output = measure_part(part, &**parsed, start);
+            // A worker that was given up on has already been replaced
+            if !finish_part(&progress[index], &timed_out) {
+                break;
+            }
+            if sender.send((index, Some(output))).is_err() {
+                break;
+            }
+        });
+    };
+    for _ in 0..workers {
+        spawn_worker();
+    }
+
+    let mut finished: Vec<Option<(Outcome, Measurement)>> = (0..count).map(|_| None).collect();
+    let mut remaining = count;
+    while remaining > 0 {
+        // When the earliest running part exceeds the time budget
+        let deadline = timeout.and_then(|timeout| {
+            progress
+                .iter()
+                .filter_map(|progress| match *progress.lock().unwrap() {
+                    Progress::Running(start) => Some(start + timeout),
+                    _ => None,
+                })
+                .min()
+        });
+        let received = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                let wait = if deadline > now { deadline - now } else { Duration::from_secs(0) };
+                receiver.recv_timeout(wait)
+            }
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok((_, None)) => {}
+            Ok((index, Some(output))) => {
+                finished[index] = Some(part_outcome(output));
+                remaining -= 1;
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let timeout = timeout.expect("only a part with a time budget times out");
+                for (index, progress) in progress.iter().enumerate() {
+                    let start = match *progress.lock().unwrap() {
+                        Progress::Running(start) if start.elapsed() >= timeout => start,
+                        _ => continue,
+                    };
+                    if abandon_part(progress, timed_out) {
+                        let e = Error::Timeout(timeout);
+                        let outcome = Outcome::Unfinished(Status::Timeout, e);
+                        finished[index] = Some((outcome, unfinished_measurement(start)));
+                        remaining -= 1;
+                        spawn_worker();
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => unreachable!("this thread holds a sender"),
+        }
+    }
+    finished.into_iter().map(|outcome| outcome.expect("every part finished")).collect()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+// A missing answer file has no answers yet. An unreadable one is an error
+// rather than being overwritten by the next confirm.
+fn load_answers() -> Result<HashMap<String, BTreeMap<String, Answer>>> {
+    let invalid = |message: &str| Error::InvalidFile(ANSWERS_FILE.to_owned(), message.to_owned());
+    let data = match ::std::fs::read(ANSWERS_FILE) {
+        Ok(data) => data,
+        Err(ref e) if e.kind() == ::std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let value = ::serde_json::from_slice::<Value>(&data[..]).map_err(|e| invalid(&e.to_string()))?;
+    let days = match value {
+        Value::Object(days) => days,
+        _ => return Err(invalid("answers must be a JSON object")),
+    };
+    let mut answers = HashMap::new();
+    for (url, parts) in days {
+        let parts = parts
+            .as_object()
+            .ok_or_else(|| invalid("the answers of a day must be a JSON object"))?
+            .iter()
+            .map(|(part, answer)| {
+                let answer = Answer::from_json(answer).ok_or_else(|| invalid("invalid answer"))?;
+                Ok((part.clone(), answer))
+            })
+            .collect::<Result<_>>()?;
+        answers.insert(url, parts);
+    }
+    Ok(answers)
+}
+
+fn print_json(value: Value) {
+    println!("{}", value);
+}
+
+fn memory_json(memory: Option<Usage>) -> Value {
+    match memory {
+        Some(memory) => json!({
+            "peak": memory.peak,
+            "allocated": memory.allocated,
+            "allocations": memory.allocations,
+        }),
+        None => Value::Null,
+    }
+}
+
+fn duration_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9
+}
+
+pub fn print_summary(results: &[PartResult]) {
+    // Memory columns only when allocations were counted
+    let memory = results.iter().any(|result| result.memory.is_some());
+    let memory_columns = |peak: &str, allocated: &str, allocations: &str| {
+        if memory {
+            format!(" {:>10} {:>10} {:>11}", peak, allocated, allocations)
+        } else {
+            String::new()
+        }
+    };
+
+    println!("\n{}", "summary".bright_cyan().bold());
+    println!(
+        "{:<10} {:<7} {:>12}{} {:<7} {}",
+        "day",
+        "part",
+        "time",
+        memory_columns("peak", "allocated", "allocations"),
+        "status",
+        "answer"
+    );
+    let mut total = Duration::from_secs(0);
+    let mut previous_day = None;
+    for result in results {
+        // Every day is parsed once, before its first part
+        if previous_day != Some(result.day) {
+            println!(
+                "{:<10} {:<7} {:>12}",
+                result.day,
+                "parse",
+                format_duration(result.parse_duration)
+            );
+            total += result.parse_duration;
+            previous_day = Some(result.day);
+        }
+        total += result.duration;
+
+        // Multi-line answers only show their first line
+        let answer = result.answer.as_ref().map(Answer::to_string).unwrap_or_default();
+        let mut lines = answer.lines();
+        let first_line = lines.next().unwrap_or("");
+        let ellipsis = if lines.next().is_some() { " ..." } else { "" };
+        let status = result.status.map(Status::as_str).unwrap_or("");
+        let usage = match result.memory {
+            Some(usage) => memory_columns(
+                &memory::format_bytes(usage.peak),
+                &memory::format_bytes(usage.allocated),
+                &usage.allocations.to_string(),
+            ),
+            None => memory_columns("", "", ""),
+        };
+        println!(
+            "{}",
+            format!(
+                "{:<10} {:<7} {:>12}{} {:<7} {}{}",
+                result.day,
+                result.part,
+                format_duration(result.duration),
+                usage,
+                status,
+                first_line,
+                ellipsis
+            )
+            .trim_end()
+        );
+    }
+    println!("{:<18} {:>12}", "total", format_duration(total).bold());
+}
+
+pub fn print_matrix(cells: &[MatrixCell]) {
+    let mut inputs: Vec<&str> = Vec::new();
+    let mut rows: Vec<(DayId, &'static str)> = Vec::new();
+    for cell in cells {
+        if !inputs.contains(&cell.input.as_str()) {
+            inputs.push(&cell.input);
+        }
+        if !rows.contains(&(cell.day, cell.part)) {
+            rows.push((cell.day, cell.part));
+        }
+    }
+
+    // Multi-line answers and errors only show their first line
+    let text = |cell: &MatrixCell| match cell.result {
+        Ok(ref result) => {
+            let answer = result.answer.as_ref().map(Answer::to_string).unwrap_or_default();
+            let status = result.status.map(Status::as_str).unwrap_or("");
+            format!("{} {}", answer.lines().next().unwrap_or(""), status).trim().to_owned()
+        }
+        Err(ref e) => {
+            let cause = e.lines().last().unwrap_or("").trim();
+            format!("ERROR {}", cause.trim_start_matches("caused by: "))
+        }
+    };
+    let width = cells
+        .iter()
+        .map(|cell| text(cell).len())
+        .chain(inputs.iter().map(|input| input.len()))
+        .max()
+        .unwrap_or(0);
+
+    println!("\n{}", "matrix".bright_cyan().bold());
+    let mut header = format!("{:<10} {:<7}", "day", "part");
+    for input in &inputs {
+        header.push_str(&format!(" {:<width$}", input, width = width));
+    }
+    println!("{}", header.trim_end());
+    for &(day, part) in &rows {
+        let mut row = format!("{:<10} {:<7}", day, part);
+        for input in &inputs {
+            let cell = cells
+                .iter()
+                .find(|cell| cell.day == day && cell.part == part && cell.input == *input);
+            let text = cell.map(&text).unwrap_or_default();
+            row.push_str(&format!(" {:<width$}", text, width = width));
+        }
+        println!("{}", row.trim_end());
+    }
+}
+
+pub fn print_bench_summary(results: &[BenchResult]) {
+    println!("\n{}", "summary".bright_cyan().bold());
+    println!(
+        "{:<10} {:<7} {:>12} {:>12} {:>12} {:>6}",
+        "day", "part", "min", "median", "max", "runs"
+    );
+    for result in results {
+        println!(
+            "{:<10} {:<7} {:>12} {:>12} {:>12} {:>6}",
+            result.day,
+            result.part,
+            format_duration(result.min),
+            format_duration(result.median),
+            format_duration(result.max),
+            result.iterations
+        );
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_secs() as f64 * 1e9 + duration.subsec_nanos() as f64;
+    if nanos >= 1e9 {
+        format!("{:.3}s", nanos / 1e9)
+    } else if nanos >= 1e6 {
+        format!("{:.3}ms", nanos / 1e6)
+    } else {
+        format!("{:.3}µs", nanos / 1e3)
+    }
+}
+
+fn normalize_input(mut input: String) -> String {
+    // Strip trailing newline characters
+    while let Some(last) = input.pop() {
+        if last == '\r' || last == '\n' {
+            continue;
+        }
+        input.push(last);
+        break;
+    }
+    input
+}
+
+#[test]
+fn framework_test() {
+    fn solves(_: &AnyParsed) -> Result<Answer> {
+        Ok(Answer::Integer(42))
+    }
+    fn panics(_: &AnyParsed) -> Result<Answer> {
+        panic!("no cycle found")
+    }
+    fn hangs(_: &AnyParsed) -> Result<Answer> {
+        thread::sleep(Duration::from_secs(2));
+        Ok(Answer::Integer(0))
+    }
+
+    let parsed: Arc<AnyParsed> = Arc::new(());
+    let timeout = Some(Duration::from_millis(100));
+    let timed_out = Arc::new(AtomicUsize::new(0));
+    match time_part(solves, &parsed, timeout, &timed_out).0 {
+        Outcome::Finished(Ok(Answer::Integer(42))) => {}
+        _ => panic!("the part should finish"),
+    }
+    match time_part(panics, &parsed, None, &timed_out).0 {
+        Outcome::Unfinished(Status::Panic, Error::Panicked(ref message)) => {
+            assert_eq!(message, "no cycle found")
+        }
+        _ => panic!("the part should panic"),
+    }
+    let (outcome, measurement) = time_part(hangs, &parsed, timeout, &timed_out);
+    match outcome {
+        Outcome::Unfinished(Status::Timeout, Error::Timeout(_)) => {}
+        _ => panic!("the part should time out"),
+    }
+    assert!(measurement.duration < Duration::from_secs(1));
+    assert_eq!(timed_out.load(Ordering::SeqCst), 1);
+
+    // The only worker is replaced once its part times out
+    let parts = vec![(hangs as PartFn, parsed.clone()), (solves, parsed.clone())];
+    let outcomes = run_pool(parts, 1, timeout, &timed_out);
+    match outcomes[0].0 {
+        Outcome::Unfinished(Status::Timeout, Error::Timeout(_)) => {}
+        _ => panic!("the part should time out"),
+    }
+    match outcomes[1].0 {
+        Outcome::Finished(Ok(Answer::Integer(42))) => {}
+        _ => panic!("the part should finish"),
+    }
+    assert_eq!(timed_out.load(Ordering::SeqCst), 2);
+    thread::sleep(Duration::from_secs(3));
+    assert_eq!(timed_out.load(Ordering::SeqCst), 0);
 }
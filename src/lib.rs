@@ -0,0 +1,91 @@
+#![feature(stmt_expr_attributes, drain_filter, try_from)]
+#![allow(unused_imports)]
+
+extern crate colored;
+#[macro_use]
+extern crate lazy_static;
+extern crate bincode;
+extern crate crypto;
+extern crate dirs;
+extern crate num_cpus;
+extern crate num_traits;
+extern crate pathfinding;
+extern crate regex;
+extern crate reqwest;
+extern crate serde;
+#[cfg_attr(test, macro_use)]
+extern crate smallvec;
+extern crate twoway;
+#[macro_use]
+extern crate bitflags;
+
+pub mod answer;
+pub mod cache;
+pub mod cli;
+pub mod compiler;
+pub mod config;
+pub mod debugger;
+pub mod decompiler;
+pub mod elfcode;
+pub mod error;
+pub mod examples;
+pub mod fetch;
+pub mod mat2;
+pub mod memory;
+pub mod submit;
+#[cfg(test)]
+mod test_server;
+pub mod vec2;
+pub mod vec3;
+pub mod watch;
+#[macro_use]
+pub mod framework;
+
+use crate::framework::Framework;
+
+pub use crate::error::Error;
+pub use crate::error::Result;
+
+macro_rules! days {
+    ($($days:ident),+$(,)*) => {
+        $(
+            pub mod $days;
+        )+
+
+        // Registers every solved day, in order
+        pub fn register_days(fw: &mut Framework) {
+            $(
+                crate::$days::register_day(fw);
+            )+
+        }
+    };
+}
+
+#[rustfmt::skip]
+days!(
+    day01,
+    day02,
+    day03,
+    day04,
+    day05,
+    day06,
+    day07,
+    day08,
+    day09,
+    day10,
+    day11,
+    day12,
+    day13,
+    day14,
+    day15,
+    day16,
+    day17,
+    day18,
+    day19,
+    day20,
+    day21,
+    day22,
+    day23,
+    day24,
+    day25,
+);
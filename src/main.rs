@@ -1,112 +1,45 @@
-#![feature(stmt_expr_attributes, drain_filter, try_from)]
-#![allow(unused_imports)]
-
-extern crate colored;
-#[macro_use]
-extern crate lazy_static;
-extern crate bincode;
-extern crate crypto;
-extern crate num_traits;
-extern crate pathfinding;
-extern crate regex;
-extern crate reqwest;
-extern crate serde;
-#[cfg_attr(test, macro_use)]
-extern crate smallvec;
-extern crate twoway;
-#[macro_use]
-extern crate bitflags;
+extern crate advent_of_code_2018;
 extern crate ansi_term;
+extern crate colored;
 
-mod error;
-mod mat2;
-mod vec2;
-mod vec3;
-#[macro_use]
-mod framework;
-
+use advent_of_code_2018::cli;
+use advent_of_code_2018::framework::Framework;
+use advent_of_code_2018::memory::CountingAllocator;
+use advent_of_code_2018::Error;
 use colored::*;
-use crate::framework::Framework;
-use reqwest::Client;
 use std::env;
 
-pub(crate) use crate::error::Error;
-pub(crate) use crate::error::Result;
-
-macro_rules! main {
-    ($($days:ident),+$(,)*) => {
-        $(
-            mod $days;
-        )+
-        fn main() {
-            if cfg!(windows) && !ansi_term::enable_ansi_support().is_ok() {
-                colored::control::set_override(false);
-            }
-
-            let mut fw = Framework::new();
-
-            $(
-                {
-                    crate::$days::register_day(&mut fw);
-                }
-            )+;
+// Only counts once --memory enables it
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
-            let client = Client::new();
+fn main() {
+    if cfg!(windows) && !ansi_term::enable_ansi_support().is_ok() {
+        colored::control::set_override(false);
+    }
 
-            let args: Vec<String> = env::args().collect();
-            match args.len() {
-                1 => {
-                    // execute all
-                    $(
-                        {
-                            if let Err(e) = fw.execute(&client, stringify!($days)) {
-                                eprintln!("{}", e.to_string().red());
-                                std::process::exit(-2);
-                            }
-                        }
-                    )+;
-                },
-                2 => {
-                    // execute specific day
-                    if let Err(e) = fw.execute(&client, args[1].as_str()) {
-                        eprintln!("{}", e.to_string().bright_red());
-                        std::process::exit(-2);
-                    }
-                },
-                _ => {
-                    eprintln!("too many arguments");
-                    std::process::exit(-1);
-                }
+    let mut fw = match Framework::new() {
+        Ok(fw) => fw,
+        Err(e) => {
+            eprintln!("{}", e.report().bright_red());
+            std::process::exit(e.exit_code());
+        }
+    };
+    advent_of_code_2018::register_days(&mut fw);
+
+    let invocation = match cli::parse(&fw, env::args().skip(1)) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("{}", e.report().bright_red());
+            if let Error::Usage(_) = e {
+                cli::print_usage();
             }
+            std::process::exit(e.exit_code());
         }
     };
-}
 
-#[rustfmt::skip]
-main!(
-    day01,
-    day02,
-    day03,
-    day04,
-    day05,
-    day06,
-    day07,
-    day08,
-    day09,
-    day10,
-    day11,
-    day12,
-    day13,
-    day14,
-    day15,
-    day16,
-    day17,
-    day18,
-    day19,
-    day20,
-    day21,
-    day22,
-    day23,
-    day24,
-    day25,
-);
+    if let Err(e) = cli::execute(&mut fw, invocation) {
+        eprintln!("{}", e.report().bright_red());
+        std::process::exit(e.exit_code());
+    }
+}
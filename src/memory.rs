@@ -0,0 +1,173 @@
+// Heap usage of the parts, counted by a global allocator. The runner installs
+// `CountingAllocator`, which only counts once `enable` was called, so runs that
+// do not report memory usage pay for little more than a load per allocation.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static COUNTERS: Counters = Counters::new();
+
+pub struct CountingAllocator;
+
+struct Counters {
+    allocations: AtomicUsize,
+    allocated: AtomicUsize,
+    // Memory allocated before counting started may be freed while counting, so
+    // the current usage can drop below zero
+    current: AtomicIsize,
+    peak: AtomicIsize,
+}
+
+impl Counters {
+    const fn new() -> Counters {
+        Counters {
+            allocations: AtomicUsize::new(0),
+            allocated: AtomicUsize::new(0),
+            current: AtomicIsize::new(0),
+            peak: AtomicIsize::new(0),
+        }
+    }
+
+    fn record(&self, allocated: usize, freed: usize) {
+        if allocated > 0 {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            self.allocated.fetch_add(allocated, Ordering::Relaxed);
+        }
+        let delta = allocated as isize - freed as isize;
+        let current = self.current.fetch_add(delta, Ordering::Relaxed) + delta;
+        let mut peak = self.peak.load(Ordering::Relaxed);
+        while current > peak {
+            let (success, failure) = (Ordering::Relaxed, Ordering::Relaxed);
+            match self.peak.compare_exchange_weak(peak, current, success, failure) {
+                Ok(_) => break,
+                Err(actual) => peak = actual,
+            }
+        }
+    }
+
+    fn measure<T, F: FnOnce() -> T>(&self, f: F) -> (T, Usage) {
+        let allocations = self.allocations.load(Ordering::SeqCst);
+        let allocated = self.allocated.load(Ordering::SeqCst);
+        let current = self.current.load(Ordering::SeqCst);
+        self.peak.store(current, Ordering::SeqCst);
+        let result = f();
+        let usage = Usage {
+            peak: (self.peak.load(Ordering::SeqCst) - current).max(0) as usize,
+            allocated: self.allocated.load(Ordering::SeqCst) - allocated,
+            allocations: self.allocations.load(Ordering::SeqCst) - allocations,
+        };
+        (result, usage)
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() && ENABLED.load(Ordering::Relaxed) {
+            COUNTERS.record(layout.size(), 0);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() && ENABLED.load(Ordering::Relaxed) {
+            COUNTERS.record(layout.size(), 0);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        if ENABLED.load(Ordering::Relaxed) {
+            COUNTERS.record(0, layout.size());
+        }
+    }
+
+    // A reallocation counts as a new allocation of the new size
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() && ENABLED.load(Ordering::Relaxed) {
+            COUNTERS.record(new_size, layout.size());
+        }
+        new_ptr
+    }
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub peak: usize,        // bytes in use at once, beyond what was in use before
+    pub allocated: usize,   // bytes over all allocations
+    pub allocations: usize,
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} peak, {} in {} allocations",
+            format_bytes(self.peak),
+            format_bytes(self.allocated),
+            self.allocations
+        )
+    }
+}
+
+// The counters are shared by every thread, so measurements are only accurate
+// while nothing else allocates at the same time
+pub fn measure<T, F: FnOnce() -> T>(f: F) -> (T, Option<Usage>) {
+    if !is_enabled() {
+        return (f(), None);
+    }
+
+    let (result, usage) = COUNTERS.measure(f);
+    (result, Some(usage))
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    let bytes = bytes as f64;
+    if bytes >= 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.1}GiB", bytes / (1024.0 * 1024.0 * 1024.0))
+    } else if bytes >= 1024.0 * 1024.0 {
+        format!("{:.1}MiB", bytes / (1024.0 * 1024.0))
+    } else if bytes >= 1024.0 {
+        format!("{:.1}KiB", bytes / 1024.0)
+    } else {
+        format!("{}B", bytes)
+    }
+}
+
+#[test]
+fn memory_test() {
+    assert_eq!(format_bytes(512), "512B");
+    assert_eq!(format_bytes(1536), "1.5KiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024), "3.0MiB");
+
+    let (_, usage) = measure(|| ());
+    assert_eq!(usage, None);
+
+    // Tests do not install the allocator, so they count on their own
+    let counters = Counters::new();
+    counters.record(0, 64);
+    let (_, usage) = counters.measure(|| {
+        counters.record(100, 0);
+        counters.record(200, 100);
+        counters.record(0, 200);
+        counters.record(50, 0);
+    });
+    let expected = Usage {
+        peak: 200,
+        allocated: 350,
+        allocations: 3,
+    };
+    assert_eq!(usage, expected);
+}
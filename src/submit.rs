@@ -0,0 +1,397 @@
+// Answer submission, with a local history of every verdict so answers that are
+// known to be wrong are never submitted again
+use crate::cache;
+use crate::fetch::Fetcher;
+use crate::{Error, Result};
+use colored::*;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+pub const HISTORY_FILE: &str = "submissions.json";
+// How often an answer is posted while the server reports a cooldown, and how
+// long to wait when it does not say for how long
+const MAX_ATTEMPTS: usize = 3;
+const DEFAULT_COOLDOWN_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    TooHigh,
+    TooLow,
+    Wrong,
+    TooRecent,     // not judged, the cooldown of an earlier submission is still running
+    AlreadySolved, // the part was solved before, possibly somewhere else
+}
+
+impl Verdict {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Correct => "correct",
+            Verdict::TooHigh => "too high",
+            Verdict::TooLow => "too low",
+            Verdict::Wrong => "wrong",
+            Verdict::TooRecent => "too recent",
+            Verdict::AlreadySolved => "already solved",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Verdict> {
+        [
+            Verdict::Correct,
+            Verdict::TooHigh,
+            Verdict::TooLow,
+            Verdict::Wrong,
+            Verdict::TooRecent,
+            Verdict::AlreadySolved,
+        ]
+        .iter()
+        .cloned()
+        .find(|verdict| verdict.as_str() == s)
+    }
+
+    pub fn is_wrong(self) -> bool {
+        match self {
+            Verdict::TooHigh | Verdict::TooLow | Verdict::Wrong => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub verdict: Verdict,
+    pub wait: Option<Duration>, // before the next answer may be submitted
+}
+
+pub fn parse_response(html: &str) -> Result<Response> {
+    lazy_static! {
+        static ref TIME_LEFT: Regex =
+            Regex::new(r"You have (?:(?P<m>\d+)m )?(?P<s>\d+)s left to wait").unwrap();
+        static ref WAIT_MINUTES: Regex =
+            Regex::new(r"[Pp]lease wait (?P<m>one|\d+) minutes? before trying again").unwrap();
+    }
+
+    let verdict = if html.contains("That's the right answer") {
+        Verdict::Correct
+    } else if html.contains("You gave an answer too recently") {
+        Verdict::TooRecent
+    } else if html.contains("You don't seem to be solving the right level") {
+        Verdict::AlreadySolved
+    } else if html.contains("That's not the right answer") {
+        if html.contains("your answer is too high") {
+            Verdict::TooHigh
+        } else if html.contains("your answer is too low") {
+            Verdict::TooLow
+        } else {
+            Verdict::Wrong
+        }
+    } else {
+        return Err(Error::UnexpectedResponse);
+    };
+
+    let wait = if let Some(c) = TIME_LEFT.captures(html) {
+        let minutes = c.name("m").map_or(Ok(0), |m| m.as_str().parse::<u64>())?;
+        Some(Duration::from_secs(minutes * 60 + c["s"].parse::<u64>()?))
+    } else if let Some(c) = WAIT_MINUTES.captures(html) {
+        let minutes = match &c["m"] {
+            "one" => 1,
+            m => m.parse::<u64>()?,
+        };
+        Some(Duration::from_secs(minutes * 60))
+    } else {
+        None
+    };
+
+    Ok(Response { verdict, wait })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submission {
+    pub part: String,
+    pub answer: String,
+    pub verdict: Verdict,
+    pub submitted_at: u64, // seconds since the unix epoch
+}
+
+impl Submission {
+    fn to_json(&self) -> Value {
+        json!({
+            "part": self.part,
+            "answer": self.answer,
+            "verdict": self.verdict.as_str(),
+            "submitted_at": self.submitted_at,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Submission> {
+        Some(Submission {
+            part: value.get("part")?.as_str()?.to_owned(),
+            answer: value.get("answer")?.as_str()?.to_owned(),
+            verdict: Verdict::parse(value.get("verdict")?.as_str()?)?,
+            submitted_at: value.get("submitted_at")?.as_u64()?,
+        })
+    }
+}
+
+// Submissions per day, keyed by the URL of the day's input like the answers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    submissions: BTreeMap<String, Vec<Submission>>,
+    wait_until: u64, // seconds since the unix epoch
+}
+
+impl History {
+    pub fn read(path: &Path) -> Result<History> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(History::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let invalid = |message: &str| {
+            Error::InvalidFile(path.display().to_string(), message.to_owned())
+        };
+        let value =
+            ::serde_json::from_slice::<Value>(&data[..]).map_err(|e| invalid(&e.to_string()))?;
+        let mut history = History::default();
+        history.wait_until = value.get("wait_until").and_then(Value::as_u64).unwrap_or(0);
+        if let Some(Value::Object(days)) = value.get("submissions") {
+            for (url, submissions) in days {
+                let submissions = submissions
+                    .as_array()
+                    .ok_or_else(|| invalid("submissions must be an array"))?
+                    .iter()
+                    .map(|submission| {
+                        Submission::from_json(submission)
+                            .ok_or_else(|| invalid("invalid submission"))
+                    })
+                    .collect::<Result<_>>()?;
+                history.submissions.insert(url.clone(), submissions);
+            }
+        }
+        Ok(history)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let submissions = self
+            .submissions
+            .iter()
+            .map(|(url, submissions)| {
+                let submissions = submissions.iter().map(Submission::to_json).collect();
+                (url.clone(), Value::Array(submissions))
+            })
+            .collect::<::serde_json::Map<_, _>>();
+        let value = json!({
+            "wait_until": self.wait_until,
+            "submissions": submissions,
+        });
+        fs::write(path, ::serde_json::to_string_pretty(&value)?)?;
+        Ok(())
+    }
+
+    pub fn submissions(&self, url: &str) -> &[Submission] {
+        self.submissions.get(url).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn record(&mut self, url: &str, submission: Submission) {
+        self.submissions
+            .entry(url.to_owned())
+            .or_insert_with(Vec::new)
+            .push(submission);
+    }
+
+    // The answer a part was solved with. The server does not judge answers to
+    // parts that were solved before, so those count as solved too.
+    pub fn solution(&self, url: &str, part: &str) -> Option<&str> {
+        let submissions = || self.submissions(url).iter().filter(move |s| s.part == part);
+        submissions()
+            .find(|s| s.verdict == Verdict::Correct)
+            .or_else(|| submissions().find(|s| s.verdict == Verdict::AlreadySolved))
+            .map(|s| s.answer.as_str())
+    }
+
+    // Why submitting the answer is pointless, according to earlier verdicts
+    pub fn check(&self, url: &str, part: &str, answer: &str) -> Option<String> {
+        let value = answer.parse::<i64>().ok();
+        for submission in self.submissions(url).iter().filter(|s| s.part == part) {
+            if submission.answer == answer && submission.verdict.is_wrong() {
+                return Some(format!("{} was rejected before", answer));
+            }
+            let bound = match submission.answer.parse::<i64>() {
+                Ok(bound) => bound,
+                Err(_) => continue,
+            };
+            match (value, submission.verdict) {
+                (Some(value), Verdict::TooHigh) if value >= bound => {
+                    return Some(format!("{} is not below {}, which is too high", answer, bound));
+                }
+                (Some(value), Verdict::TooLow) if value <= bound => {
+                    return Some(format!("{} is not above {}, which is too low", answer, bound));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    pub fn wait_until(&self) -> u64 {
+        self.wait_until
+    }
+}
+
+// Posts the answer to the answer endpoint at `url`, and records the verdict in
+// the history under `key`. Waits out the cooldown of earlier submissions first,
+// and submits again when the server still reports one, up to `MAX_ATTEMPTS`
+// times.
+pub fn submit(
+    fetcher: &mut Fetcher,
+    history: &mut History,
+    key: &str,
+    url: &str,
+    token: Option<&str>,
+    part: &str,
+    answer: &str,
+) -> Result<Verdict> {
+    if let Some(reason) = history.check(key, part, answer) {
+        return Err(Error::AnswerRejected(reason));
+    }
+    let level = match part {
+        "part1" => "1",
+        "part2" => "2",
+        _ => return Err(Error::Usage(format!("cannot submit {}", part))),
+    };
+
+    for _ in 0..MAX_ATTEMPTS {
+        let now = cache::now();
+        if history.wait_until > now {
+            let wait = history.wait_until - now;
+            println!("{}", format!("waiting {}s before submitting", wait).yellow());
+            thread::sleep(Duration::from_secs(wait));
+        }
+
+        let html = fetcher.post(url, token, &[("level", level), ("answer", answer)])?;
+        let response = parse_response(&html)?;
+        if let Some(wait) = response.wait {
+            history.wait_until = cache::now() + wait.as_secs();
+        }
+        if response.verdict == Verdict::TooRecent {
+            if response.wait.is_none() {
+                history.wait_until = cache::now() + DEFAULT_COOLDOWN_SECS;
+            }
+            continue;
+        }
+
+        history.record(
+            key,
+            Submission {
+                part: part.to_owned(),
+                answer: answer.to_owned(),
+                verdict: response.verdict,
+                submitted_at: cache::now(),
+            },
+        );
+        return Ok(response.verdict);
+    }
+    Err(Error::AnswerRejected(format!(
+        "the server still reported a cooldown after {} attempts",
+        MAX_ATTEMPTS
+    )))
+}
+
+#[test]
+fn submit_test() {
+    use crate::test_server::TestServer;
+    use reqwest::Client;
+
+    const CORRECT: &str = "<article><p>That's the right answer! You are one gold star \
+                           closer.</p></article>";
+    const TOO_HIGH: &str = "<article><p>That's not the right answer; your answer is too high. \
+                            Please wait one minute before trying again.</p></article>";
+    const TOO_RECENT: &str = "<article><p>You gave an answer too recently; you have to wait \
+                              after submitting an answer before trying again. You have 1s left \
+                              to wait.</p></article>";
+
+    let response = |html| parse_response(html).unwrap();
+    assert_eq!(response(CORRECT), Response { verdict: Verdict::Correct, wait: None });
+    assert_eq!(
+        response(TOO_HIGH),
+        Response { verdict: Verdict::TooHigh, wait: Some(Duration::from_secs(60)) }
+    );
+    assert_eq!(
+        response("You gave an answer too recently. You have 4m 36s left to wait."),
+        Response { verdict: Verdict::TooRecent, wait: Some(Duration::from_secs(276)) }
+    );
+    assert_eq!(
+        response("That's not the right answer. Please wait 5 minutes before trying again.").wait,
+        Some(Duration::from_secs(300))
+    );
+    assert_eq!(
+        response("You don't seem to be solving the right level.").verdict,
+        Verdict::AlreadySolved
+    );
+    assert!(parse_response("<html>Puzzle inputs differ by user.</html>").is_err());
+
+    // Known wrong answers and answers beyond known bounds are never submitted
+    let key = "https://adventofcode.com/2018/day/1/input";
+    let mut history = History::default();
+    let wrong = |answer: &str, verdict| Submission {
+        part: "part1".to_owned(),
+        answer: answer.to_owned(),
+        verdict,
+        submitted_at: 0,
+    };
+    history.record(key, wrong("500", Verdict::TooHigh));
+    history.record(key, wrong("100", Verdict::TooLow));
+    history.record(key, wrong("abc", Verdict::Wrong));
+    assert!(history.check(key, "part1", "abc").is_some());
+    assert!(history.check(key, "part1", "500").is_some());
+    assert!(history.check(key, "part1", "650").is_some());
+    assert!(history.check(key, "part1", "100").is_some());
+    assert!(history.check(key, "part1", "250").is_none());
+    assert!(history.check(key, "part2", "650").is_none());
+
+    // Waits out the cooldown and submits again
+    let server = TestServer::start(vec![(200, TOO_RECENT), (200, CORRECT)]);
+    let mut fetcher = Fetcher::new(Client::new()).with_min_interval(Duration::from_millis(0));
+    let url = format!("{}/2018/day/1/answer", server.url);
+    let verdict = submit(&mut fetcher, &mut history, key, &url, Some("abc"), "part1", "250");
+    assert_eq!(verdict.unwrap(), Verdict::Correct);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].starts_with("POST /2018/day/1/answer "));
+    assert!(requests[1].ends_with("level=1&answer=250"));
+    assert_eq!(history.solution(key, "part1"), Some("250"));
+    assert_eq!(history.submissions(key).len(), 4);
+
+    match submit(&mut fetcher, &mut history, key, &url, Some("abc"), "part1", "500") {
+        Err(Error::AnswerRejected(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // Gives up when the cooldown does not pass
+    let server = TestServer::start(vec![(200, TOO_RECENT); MAX_ATTEMPTS]);
+    let url = format!("{}/2018/day/1/answer", server.url);
+    match submit(&mut fetcher, &mut history, key, &url, Some("abc"), "part2", "7") {
+        Err(Error::AnswerRejected(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(server.requests().len(), MAX_ATTEMPTS);
+    assert_eq!(history.submissions(key).len(), 4);
+
+    // A part the server says was solved before is not submitted again
+    assert_eq!(history.solution(key, "part2"), None);
+    let solved = wrong("7", Verdict::AlreadySolved);
+    history.record(key, Submission { part: "part2".to_owned(), ..solved });
+    assert_eq!(history.solution(key, "part2"), Some("7"));
+
+    let path = ::std::env::temp_dir().join(format!("aoc_submit_test_{}", ::std::process::id()));
+    fs::write(&path, "{\"submissions\": {\"a\": 1}}").unwrap();
+    let e = History::read(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+    assert!(e.to_string().contains("aoc_submit_test_"));
+}
@@ -0,0 +1,78 @@
+// Minimal HTTP stand-in server for tests, answers every connection with the next scripted response
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub struct TestServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+fn read_request<R: Read>(stream: &mut R) -> String {
+    let mut data = Vec::new();
+    let mut buffer = [0u8; 1024];
+    loop {
+        let count = match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(count) => count,
+        };
+        data.extend_from_slice(&buffer[..count]);
+
+        let text = String::from_utf8_lossy(&data).into_owned();
+        if let Some(head_end) = text.find("\r\n\r\n") {
+            let content_length = text[..head_end]
+                .lines()
+                .filter_map(|line| {
+                    let mut parts = line.splitn(2, ':');
+                    match (parts.next(), parts.next()) {
+                        (Some(name), Some(value))
+                            if name.eq_ignore_ascii_case("content-length") =>
+                        {
+                            value.trim().parse::<usize>().ok()
+                        }
+                        _ => None,
+                    }
+                })
+                .next()
+                .unwrap_or(0);
+            if data.len() >= head_end + 4 + content_length {
+                break;
+            }
+        }
+    }
+    String::from_utf8_lossy(&data).into_owned()
+}
+
+impl TestServer {
+    pub fn start(responses: Vec<(u16, &'static str)>) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let mut stream = match listener.accept() {
+                    Ok((stream, _)) => stream,
+                    Err(_) => return,
+                };
+                let request = read_request(&mut stream);
+                recorded.lock().unwrap().push(request);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} Scripted\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+            }
+        });
+
+        TestServer { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,150 @@
+// Polling file watcher, so watching works the same on every platform
+use colored::*;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+pub const DEFAULT_INTERVAL_MS: u64 = 500;
+
+type Stamp = Option<(SystemTime, u64)>; // None while the file does not exist
+
+fn stamp(path: &PathBuf) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    stamps: Vec<Stamp>,
+    interval: Duration,
+}
+
+impl Watcher {
+    pub fn new(interval: Duration) -> Watcher {
+        Watcher {
+            paths: Vec::new(),
+            stamps: Vec::new(),
+            interval,
+        }
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    // Watched files can change between runs, like an input that was downloaded
+    pub fn set_paths(&mut self, paths: Vec<PathBuf>) {
+        self.stamps = paths.iter().map(stamp).collect();
+        self.paths = paths;
+    }
+
+    pub fn poll(&mut self) -> bool {
+        let stamps = self.paths.iter().map(stamp).collect::<Vec<_>>();
+        let changed = stamps != self.stamps;
+        self.stamps = stamps;
+        changed
+    }
+
+    // Editors often write a file in several steps, so a change only counts
+    // once the files stayed the same for a whole interval
+    pub fn wait_for_change(&mut self) {
+        while !self.poll() {
+            thread::sleep(self.interval);
+        }
+        thread::sleep(self.interval);
+        while self.poll() {
+            thread::sleep(self.interval);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// Line based diff from the longest common subsequence of both outputs
+pub fn diff_lines<'a>(old: &'a [String], new: &'a [String]) -> Vec<Change<'a>> {
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            changes.push(Change::Same(&old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(Change::Removed(&old[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(&new[j]));
+            j += 1;
+        }
+    }
+    changes
+}
+
+pub fn print_diff(old: &[String], new: &[String]) {
+    let changes = diff_lines(old, new);
+    if changes.iter().all(|change| match change {
+        Change::Same(_) => true,
+        _ => false,
+    }) {
+        println!("{}", "no changes since the previous run".dimmed());
+        return;
+    }
+
+    println!("{}", "changes since the previous run".bright_cyan().bold());
+    for change in changes {
+        match change {
+            Change::Same(line) => println!("  {}", line),
+            Change::Removed(line) => println!("{}", format!("- {}", line).bright_red()),
+            Change::Added(line) => println!("{}", format!("+ {}", line).bright_green()),
+        }
+    }
+}
+
+#[test]
+fn watch_test() {
+    let lines = |text: &str| text.lines().map(str::to_owned).collect::<Vec<_>>();
+    let old = lines("part1 5 PASS\npart2 7 NEW");
+    let new = lines("part1 5 PASS\npart2 8 FAIL\nexpected 7");
+    assert_eq!(
+        diff_lines(&old, &new),
+        vec![
+            Change::Same("part1 5 PASS"),
+            Change::Removed("part2 7 NEW"),
+            Change::Added("part2 8 FAIL"),
+            Change::Added("expected 7"),
+        ]
+    );
+    assert_eq!(diff_lines(&old, &old).len(), 2);
+    assert_eq!(
+        diff_lines(&[], &old),
+        vec![Change::Added("part1 5 PASS"), Change::Added("part2 7 NEW")]
+    );
+
+    let path = ::std::env::temp_dir().join(format!("watch_test_{}.txt", ::std::process::id()));
+    let _ = fs::remove_file(&path);
+    let mut watcher = Watcher::new(Duration::from_millis(1));
+    watcher.set_paths(vec![path.clone()]);
+    assert!(!watcher.poll());
+    fs::write(&path, "a").unwrap();
+    assert!(watcher.poll());
+    assert!(!watcher.poll());
+    fs::remove_file(&path).unwrap();
+    assert!(watcher.poll());
+}